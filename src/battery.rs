@@ -0,0 +1,258 @@
+/* Collect battery info */
+use crate::Result;
+use log::debug;
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt, fs,
+    io::{Error, ErrorKind},
+    path::Path,
+};
+
+/// Where the kernel exposes batteries and AC adapters
+pub const POWER_SUPPLY: &str = "/sys/class/power_supply";
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BatteryState {
+    Charging,
+    Discharging,
+    Full,
+    /// Plugged in but not charging, e.g. due to a charge threshold
+    NotCharging,
+    Unknown,
+}
+
+impl BatteryState {
+    fn parse(status: &str) -> BatteryState {
+        match status {
+            "Charging" => BatteryState::Charging,
+            "Discharging" => BatteryState::Discharging,
+            "Full" => BatteryState::Full,
+            "Not charging" => BatteryState::NotCharging,
+            _ => BatteryState::Unknown,
+        }
+    }
+}
+
+impl fmt::Display for BatteryState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            BatteryState::Charging => "charging",
+            BatteryState::Discharging => "discharging",
+            BatteryState::Full => "full",
+            BatteryState::NotCharging => "not charging",
+            BatteryState::Unknown => "unknown",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Battery {
+    /// Name of the power supply, e.g. `BAT0`
+    pub name:           String,
+    /// Charge as a percentage of full capacity
+    pub capacity:       f32,
+    pub state:          BatteryState,
+    /// Seconds until empty (discharging) or full (charging), if known
+    pub time_remaining: Option<u64>,
+}
+
+impl Battery {
+    /// Read battery info from a power supply directory, e.g. `.../BAT0`
+    fn read(dir: &Path) -> Result<Battery> {
+        let read = |file: &str| {
+            fs::read_to_string(dir.join(file))
+                .ok()
+                .map(|s| s.trim().to_string())
+        };
+        let num = |file: &str| read(file).and_then(|s| s.parse::<f64>().ok());
+
+        // batteries report either energy (µWh, µW) or charge (µAh, µA)
+        let (now, full, rate) = match num("energy_now") {
+            Some(now) => (Some(now), num("energy_full"), num("power_now")),
+            None => (num("charge_now"), num("charge_full"), num("current_now")),
+        };
+        let state = BatteryState::parse(&read("status").unwrap_or_default());
+        let capacity = match (now, full) {
+            (Some(now), Some(full)) if full > 0.0 => (now / full * 100.0).min(100.0) as f32,
+            _ => num("capacity").ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("no capacity found in {:?}", dir),
+                )
+            })? as f32,
+        };
+        let hours = match (state, now, full, rate) {
+            (_, _, _, Some(rate)) if rate <= 0.0 => None,
+            (BatteryState::Discharging, Some(now), _, Some(rate)) => Some(now / rate),
+            (BatteryState::Charging, Some(now), Some(full), Some(rate)) => {
+                Some((full - now).max(0.0) / rate)
+            }
+            _ => None,
+        };
+        Ok(Battery {
+            name: dir
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            capacity,
+            state,
+            time_remaining: hours.map(|hours| (hours * 3600.0).round() as u64),
+        })
+    }
+}
+
+/// Whether `dir` is a battery that powers the system
+///
+/// Batteries of peripherals such as wireless mice (`scope` `Device`) are
+/// skipped, since they often only report a coarse `capacity_level`.
+fn is_system_battery(dir: &Path) -> bool {
+    let read = |file: &str| fs::read_to_string(dir.join(file)).map(|s| s.trim().to_string());
+    read("type").is_ok_and(|t| t == "Battery") && read("scope").map_or(true, |s| s != "Device")
+}
+
+/// All readable batteries found under `root` (normally `POWER_SUPPLY`)
+pub fn get_batteries_from<P: AsRef<Path>>(root: P) -> Result<Vec<Battery>> {
+    let mut dirs = fs::read_dir(root.as_ref())?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    dirs.sort();
+    Ok(dirs
+        .iter()
+        .filter(|dir| is_system_battery(dir))
+        .filter_map(|dir| {
+            Battery::read(dir)
+                .map_err(|e| debug!("Skipping battery {:?}: {}", dir, e))
+                .ok()
+        })
+        .collect())
+}
+
+/// Battery named `name`, or the first battery if `None`
+pub fn get_battery(name: Option<&str>) -> Result<Battery> {
+    get_battery_from(POWER_SUPPLY, name)
+}
+
+/// Battery named `name` under `root`, or the first battery if `None`
+///
+/// A named battery is read directly, so errors reading it are returned.
+pub fn get_battery_from<P: AsRef<Path>>(root: P, name: Option<&str>) -> Result<Battery> {
+    let not_found = || {
+        let msg = match name {
+            Some(name) => format!("no battery named `{}` found", name),
+            None => String::from("no battery found"),
+        };
+        Error::new(ErrorKind::NotFound, msg).into()
+    };
+    match name {
+        Some(name) => {
+            let dir = root.as_ref().join(name);
+            if !is_system_battery(&dir) {
+                return Err(not_found());
+            }
+            Battery::read(&dir)
+        }
+        None => get_batteries_from(root)?
+            .into_iter()
+            .next()
+            .ok_or_else(not_found),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::{fixture_dir, write_fixture};
+    use std::path::PathBuf;
+
+    /// Fresh power supply root, unique per test
+    fn root(test: &str) -> PathBuf {
+        fixture_dir(&format!("battery-{}", test))
+    }
+
+    fn supply(root: &Path, name: &str, files: &[(&str, &str)]) {
+        write_fixture(&root.join(name), files);
+    }
+
+    #[test]
+    fn energy_discharging() {
+        let root = root("energy");
+        supply(&root, "BAT0", &[
+            ("type", "Battery"),
+            ("status", "Discharging"),
+            ("energy_now", "25000000"),
+            ("energy_full", "50000000"),
+            ("power_now", "10000000"),
+        ]);
+        let battery = get_battery_from(&root, None).unwrap();
+        assert_eq!(battery.name, "BAT0");
+        assert_eq!(battery.capacity, 50.0);
+        assert_eq!(battery.state, BatteryState::Discharging);
+        assert_eq!(battery.time_remaining, Some(9000));
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn charge_charging() {
+        let root = root("charge");
+        supply(&root, "BAT1", &[
+            ("type", "Battery"),
+            ("status", "Charging"),
+            ("charge_now", "3000000"),
+            ("charge_full", "4000000"),
+            ("current_now", "2000000"),
+        ]);
+        let battery = get_battery_from(&root, Some("BAT1")).unwrap();
+        assert_eq!(battery.capacity, 75.0);
+        assert_eq!(battery.state, BatteryState::Charging);
+        assert_eq!(battery.time_remaining, Some(1800));
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn capacity_fallback_and_zero_rate() {
+        let root = root("fallback");
+        supply(&root, "BAT0", &[
+            ("type", "Battery"),
+            ("status", "Discharging"),
+            ("capacity", "42"),
+            ("power_now", "0"),
+        ]);
+        let battery = get_battery_from(&root, None).unwrap();
+        assert_eq!(battery.capacity, 42.0);
+        assert_eq!(battery.time_remaining, None);
+
+        supply(&root, "BAT1", &[
+            ("type", "Battery"),
+            ("status", "Discharging"),
+            ("energy_now", "1000000"),
+            ("energy_full", "2000000"),
+            ("power_now", "0"),
+        ]);
+        let battery = get_battery_from(&root, Some("BAT1")).unwrap();
+        assert_eq!(battery.capacity, 50.0);
+        assert_eq!(battery.time_remaining, None);
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn skips_devices_and_unreadable() {
+        let root = root("skip");
+        supply(&root, "AC", &[("type", "Mains"), ("online", "1")]);
+        supply(&root, "BAT0", &[("type", "Battery"), ("status", "Unknown")]);
+        supply(&root, "BAT1", &[("type", "Battery"), ("status", "Full"), ("capacity", "100")]);
+        supply(&root, "hidpp_battery_0", &[
+            ("type", "Battery"),
+            ("scope", "Device"),
+            ("capacity", "90"),
+        ]);
+        let batteries = get_batteries_from(&root).unwrap();
+        let names: Vec<_> = batteries.iter().map(|b| b.name.as_str()).collect();
+        assert_eq!(names, ["BAT1"]);
+        assert_eq!(get_battery_from(&root, None).unwrap().name, "BAT1");
+        assert!(get_battery_from(&root, Some("BAT0")).is_err());
+        assert!(get_battery_from(&root, Some("hidpp_battery_0")).is_err());
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,121 @@
+/* Detect resource limits of the current cgroup, e.g. inside containers */
+//! Supports the unified (v2) hierarchy and the v1 `memory`, `cpu` and
+//! `cpuacct` controllers. Every function returns `None` when the process is
+//! not constrained, so callers can fall back to host-wide values.
+use crate::memory::MemStats;
+use log::debug;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+
+/// Directory of the current process's cgroup for `controller` (`None` for v2)
+fn cgroup_dir(controller: Option<&str>) -> Option<PathBuf> {
+    let cgroups = fs::read_to_string("/proc/self/cgroup").ok()?;
+    let (mount, path) = cgroups.lines().find_map(|line| {
+        // e.g. `0::/user.slice` (v2) or `4:memory:/docker/abc` (v1)
+        let mut fields = line.splitn(3, ':');
+        let (_, controllers, path) = (fields.next()?, fields.next()?, fields.next()?);
+        match controller {
+            None if controllers.is_empty() => Some((PathBuf::from(CGROUP_ROOT), path)),
+            Some(controller) if controllers.split(',').any(|c| c == controller) => {
+                Some((Path::new(CGROUP_ROOT).join(controllers), path))
+            }
+            _ => None,
+        }
+    })?;
+    // without a cgroup namespace the path is relative to the host's root;
+    // with one, our cgroup is mounted at the root itself
+    let dir = mount.join(path.trim_start_matches('/'));
+    let dir = if dir.is_dir() { dir } else { mount };
+    debug!("Cgroup dir for {:?}: {:?}", controller, dir);
+    Some(dir).filter(|dir| dir.is_dir())
+}
+
+fn read_u64<P: AsRef<Path>>(path: P) -> Option<u64> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+/// Value of `key` in a flat keyed file like `memory.stat`
+fn read_stat<P: AsRef<Path>>(path: P, key: &str) -> Option<u64> {
+    fs::read_to_string(path).ok()?.lines().find_map(|line| {
+        let mut fields = line.split_whitespace();
+        if fields.next()? == key {
+            fields.next()?.parse().ok()
+        } else {
+            None
+        }
+    })
+}
+
+/// Memory limit and usage of the current cgroup, if it has a limit
+///
+/// Usage excludes inactive page cache, which the kernel reclaims before
+/// hitting the limit (like `docker stats`).
+pub fn memory() -> Option<MemStats> {
+    let (limit, usage, inactive) = if let Some(dir) = cgroup_dir(None)
+        .filter(|dir| dir.join("memory.max").exists())
+    {
+        (
+            read_u64(dir.join("memory.max"))?, // `max` if unlimited
+            read_u64(dir.join("memory.current"))?,
+            read_stat(dir.join("memory.stat"), "inactive_file").unwrap_or(0),
+        )
+    } else {
+        let dir = cgroup_dir(Some("memory"))?;
+        (
+            read_u64(dir.join("memory.limit_in_bytes"))?,
+            read_u64(dir.join("memory.usage_in_bytes"))?,
+            read_stat(dir.join("memory.stat"), "total_inactive_file").unwrap_or(0),
+        )
+    };
+    debug!(
+        "Cgroup memory: limit {}, usage {}, inactive file {}",
+        limit, usage, inactive
+    );
+    // v1 reports "unlimited" as a huge number
+    let host_total = crate::memory::get_memory().ok()?.total as u64;
+    if limit >= host_total {
+        return None;
+    }
+    // usage can briefly exceed the limit while the kernel reclaims
+    let used = usage.saturating_sub(inactive).min(limit);
+    Some(MemStats::new(limit as usize, used as usize))
+}
+
+/// Number of cpus the current cgroup may use, if it has a quota
+pub fn cpu_quota() -> Option<f64> {
+    let (quota, period) = if let Some(dir) = cgroup_dir(None)
+        .filter(|dir| dir.join("cpu.max").exists())
+    {
+        // e.g. `200000 100000` or `max 100000`
+        let max = fs::read_to_string(dir.join("cpu.max")).ok()?;
+        let mut fields = max.split_whitespace();
+        let quota = fields.next()?.parse::<f64>().ok()?;
+        (quota, fields.next()?.parse::<f64>().ok()?)
+    } else {
+        let dir = cgroup_dir(Some("cpu"))?;
+        let quota = fs::read_to_string(dir.join("cpu.cfs_quota_us")).ok()?;
+        (
+            quota.trim().parse::<f64>().ok()?, // -1 if unlimited
+            read_u64(dir.join("cpu.cfs_period_us"))? as f64,
+        )
+    };
+    debug!("Cgroup cpu: quota {}, period {}", quota, period);
+    if quota <= 0.0 || period <= 0.0 {
+        return None;
+    }
+    Some(quota / period)
+}
+
+/// Total cpu time used by the current cgroup
+pub fn cpu_usage() -> Option<Duration> {
+    if let Some(dir) = cgroup_dir(None).filter(|dir| dir.join("cpu.stat").exists()) {
+        return read_stat(dir.join("cpu.stat"), "usage_usec").map(Duration::from_micros);
+    }
+    let dir = cgroup_dir(Some("cpuacct"))?;
+    read_u64(dir.join("cpuacct.usage")).map(Duration::from_nanos)
+}
@@ -0,0 +1,120 @@
+/* Output battery info */
+use super::{print_json, toggle, toggle_opt, Context, Output};
+use getopts::Options;
+use log::debug;
+use serde_json::json;
+use sysinfo::{
+    battery::{get_battery_from, Battery, BatteryState, POWER_SUPPLY},
+    template::{self, Value},
+    Result,
+};
+
+fn print_help(command: &str, opts: Options) {
+    const DESC: &str = "Print charge of battery NAME (default: first battery found).";
+    let usage = format!(
+        "Usage: {} {} [options] [NAME]\n\n{}",
+        crate::PROG,
+        command,
+        DESC
+    );
+    print!("{}", opts.usage(&usage));
+}
+
+/// Glyph showing charge level, or a bolt if on ac power
+fn icon(battery: &Battery) -> &'static str {
+    const LEVELS: [&str; 8] = ["▁", "▂", "▃", "▄", "▅", "▆", "▇", "█"];
+    match battery.state {
+        BatteryState::Charging | BatteryState::Full | BatteryState::NotCharging => "⚡",
+        _ => {
+            let i = (battery.capacity / 100.0 * LEVELS.len() as f32) as usize;
+            LEVELS[i.min(LEVELS.len() - 1)]
+        }
+    }
+}
+
+/// Format seconds as hours and minutes, e.g. `2h05m`
+fn format_time(secs: u64) -> String {
+    format!("{}h{:02}m", secs / 3600, secs / 60 % 60)
+}
+
+pub fn main(args: &[String], ctx: &Context) -> Result {
+    debug!("Args: {:?}", args);
+    let cfg = &ctx.config.battery;
+
+    let mut opts = Options::new();
+    opts.optflag("h", "help", "print this help menu");
+    toggle_opt(&mut opts, "i", "icon", "prefix output with a charge level glyph");
+    toggle_opt(&mut opts, "s", "state", "show charging/discharging/full state");
+    opts.optopt(
+        "",
+        "root",
+        "read power supplies from DIR instead of /sys/class/power_supply",
+        "DIR",
+    );
+    opts.optopt(
+        "f",
+        "format",
+        "format output using template; fields: name, pct, state, time, \
+         hours, minutes, icon (e.g., '{icon} {pct:.0}% {time}')",
+        "TEMPLATE",
+    );
+
+    let matches = opts.parse(&args[1..])?;
+
+    if matches.opt_present("h") {
+        print_help(&args[0], opts);
+        return Ok(());
+    }
+
+    let name = matches.free.first().or(cfg.name.as_ref());
+    let root = matches
+        .opt_str("root")
+        .unwrap_or_else(|| String::from(POWER_SUPPLY));
+    let battery = get_battery_from(root, name.map(String::as_str))?;
+    debug!("Battery: {:?}", battery);
+
+    if ctx.output == Output::Json {
+        print_json(&json!({
+            "name": battery.name,
+            "capacity": battery.capacity,
+            "capacity_unit": "percent",
+            "state": battery.state,
+            "time_remaining": battery.time_remaining,
+            "time_unit": "seconds",
+        }));
+        return Ok(());
+    }
+
+    let time = battery.time_remaining.map(format_time).unwrap_or_default();
+    let out = if let Some(fmt) = matches.opt_str("f").or_else(|| cfg.format.clone()) {
+        let secs = battery.time_remaining.unwrap_or(0);
+        template::render(
+            &fmt,
+            &[
+                ("name", Value::Text(battery.name.clone())),
+                ("pct", Value::Float(battery.capacity.into())),
+                ("state", Value::Text(battery.state.to_string())),
+                ("time", Value::Text(time)),
+                ("hours", Value::Int(secs / 3600)),
+                ("minutes", Value::Int(secs / 60 % 60)),
+                ("icon", Value::Text(icon(&battery).to_string())),
+            ],
+        )?
+    } else {
+        let mut parts = Vec::new();
+        if toggle(&matches, "icon", cfg.icon) {
+            parts.push(icon(&battery).to_string());
+        }
+        parts.push(format!("{:.0}%", battery.capacity));
+        if toggle(&matches, "state", cfg.state) {
+            parts.push(battery.state.to_string());
+        }
+        if !time.is_empty() {
+            parts.push(time);
+        }
+        parts.join(" ")
+    };
+    println!("{}", out);
+
+    Ok(())
+}
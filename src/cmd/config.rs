@@ -0,0 +1,30 @@
+/* Output effective configuration */
+use super::Context;
+use getopts::Options;
+use sysinfo::Result;
+
+fn print_help(command: &str, opts: Options) {
+    const DESC: &str = "Print config file merged with defaults and global options.";
+    let usage = format!("Usage: {} {} [options]\n\n{}", crate::PROG, command, DESC);
+    print!("{}", opts.usage(&usage));
+}
+
+pub fn main(args: &[String], ctx: &Context) -> Result {
+    log::debug!("Args: {:?}", args);
+
+    let mut opts = Options::new();
+    opts.optflag("h", "help", "print this help menu");
+
+    let matches = opts.parse(&args[1..])?;
+    if matches.opt_present("h") {
+        print_help(&args[0], opts);
+        return Ok(());
+    }
+
+    match &ctx.config_path {
+        Some(path) => println!("# Loaded from {}", path.display()),
+        None => println!("# No config file found; using defaults"),
+    }
+    print!("{}", ctx.config.to_toml()?);
+    Ok(())
+}
@@ -0,0 +1,325 @@
+/* Output cpu info */
+use super::{print_json, threshold_opts, thresholds, toggle, toggle_opt, Context, Output};
+use getopts::Options;
+use serde_json::json;
+use std::time::Duration;
+use sysinfo::{
+    cgroup,
+    color::Thresholds,
+    cpu::{
+        get_cgroup_cpu, get_cgroup_cpu_since_last, get_cpu, get_cpu_breakdown,
+        get_cpu_breakdown_since_last, get_cpu_per_core, get_cpu_per_core_since_last,
+        get_cpu_since_last, CpuBreakdown, CpuStats,
+    },
+    state,
+    template::{self, Value},
+    Result,
+};
+
+fn print_help(command: &str, opts: Options) {
+    let usage = format!("Usage: {} {} [options]", crate::PROG, command);
+    print!("{}", opts.usage(&usage));
+}
+
+fn cpu_test() {
+    println!("\nMeasuring CPU load...");
+    match get_cpu_breakdown(Duration::from_secs(1)) {
+        Ok(cpu) => {
+            let rows = cpu
+                .rows()
+                .iter()
+                .map(|(label, value)| format!("{}% {}", value * 100.0, label))
+                .collect::<Vec<_>>();
+            println!("CPU load: {}", rows.join(", "));
+        }
+        Err(x) => println!("\nCPU load: error: {}", x),
+    }
+}
+
+/// Print time spent in every cpu state as a table
+///
+/// `interval` is `None` for `--saved`, whose sample length is not known.
+fn print_breakdown(
+    ctx: &Context,
+    cpu: &CpuBreakdown,
+    busy: f32,
+    interval: Option<u64>,
+) -> Result {
+    let rows = cpu.rows();
+    if ctx.output == Output::Json {
+        let mut obj = serde_json::Map::new();
+        for (label, value) in rows {
+            obj.insert(label.to_string(), json!(value));
+        }
+        obj.insert(String::from("busy"), json!(busy));
+        obj.insert(String::from("unit"), json!("fraction"));
+        if let Some(interval) = interval {
+            obj.insert(String::from("interval_secs"), json!(interval));
+        }
+        print_json(&serde_json::Value::Object(obj));
+        return Ok(());
+    }
+
+    let precision = ctx.config.cpu.precision;
+    let width = rows.iter().map(|(label, _)| label.len()).max().unwrap_or(0);
+    for (label, value) in rows {
+        let value = format!("{:.*}%", precision, f64::from(value) * 100.0);
+        println!("{:width$}  {:>7}", label, value, width = width);
+    }
+    Ok(())
+}
+
+/// One bar glyph for a busy fraction
+fn bar(busy: f32) -> &'static str {
+    const LEVELS: [&str; 8] = ["▁", "▂", "▃", "▄", "▅", "▆", "▇", "█"];
+    let i = (busy * LEVELS.len() as f32) as usize;
+    LEVELS[i.min(LEVELS.len() - 1)]
+}
+
+/// Print load of each core as selected by `--max`, `--bars` and `--cores`
+fn print_cores(
+    ctx: &Context,
+    cores: &[CpuStats],
+    show: (bool, bool, bool),
+    fmt: Option<String>,
+    thresholds: Thresholds,
+    interval: Option<u64>,
+) -> Result {
+    let (show_max, show_bars, show_cores) = show;
+    let max = cores.iter().map(CpuStats::busy).fold(0.0, f32::max);
+    if ctx.output == Output::Json {
+        let mut out = json!({
+            "cores": cores.iter().map(CpuStats::busy).collect::<Vec<_>>(),
+            "max": max,
+            "unit": "fraction",
+        });
+        if let Some(interval) = interval {
+            out["interval_secs"] = json!(interval);
+        }
+        print_json(&out);
+        return Ok(());
+    }
+
+    let precision = ctx.config.cpu.precision;
+    let level = |busy: f32| thresholds.level(f64::from(busy) * 100.0);
+    let pct = |busy: f32| format!("{:.*}%", precision, f64::from(busy) * 100.0);
+    let join = |f: &dyn Fn(f32) -> String, sep: &str| {
+        cores
+            .iter()
+            .map(|cpu| ctx.paint(&f(cpu.busy()), level(cpu.busy())))
+            .collect::<Vec<_>>()
+            .join(sep)
+    };
+    let bars = join(&|busy| bar(busy).to_string(), "");
+    let list = join(&pct, " ");
+    let out = if let Some(fmt) = fmt {
+        template::render(
+            &fmt,
+            &[
+                ("max", Value::Float(f64::from(max) * 100.0)),
+                ("bars", Value::Text(bars)),
+                ("cores", Value::Text(list)),
+                ("count", Value::Int(cores.len() as u64)),
+            ],
+        )?
+    } else {
+        let mut parts = Vec::new();
+        if show_max {
+            parts.push(ctx.paint(&pct(max), level(max)));
+        }
+        if show_bars {
+            parts.push(bars);
+        }
+        if show_cores {
+            parts.push(list);
+        }
+        parts.join(" ")
+    };
+    println!("{}", out);
+    Ok(())
+}
+
+/// Print the headline busy figure, or all of `cpu` as json or a template
+fn print_cpu(
+    ctx: &Context,
+    cpu: &CpuStats,
+    busy: f32,
+    quota: Option<f64>,
+    fmt: Option<String>,
+    thresholds: Thresholds,
+    interval: Option<u64>,
+) -> Result {
+    if ctx.output == Output::Json {
+        let mut out = json!({
+            "busy": busy,
+            "user": cpu.user,
+            "nice": cpu.nice,
+            "system": cpu.system,
+            "interrupt": cpu.interrupt,
+            "idle": cpu.idle,
+            "unit": "fraction",
+            "quota_cpus": quota,
+        });
+        if let Some(interval) = interval {
+            out["interval_secs"] = json!(interval);
+        }
+        print_json(&out);
+        return Ok(());
+    }
+
+    let busy_pct = f64::from(busy) * 100.0;
+    let out = if let Some(fmt) = fmt {
+        let pct = |n: f32| Value::Float(f64::from(n) * 100.0);
+        template::render(
+            &fmt,
+            &[
+                ("busy", pct(busy)),
+                ("user", pct(cpu.user)),
+                ("nice", pct(cpu.nice)),
+                ("system", pct(cpu.system)),
+                ("interrupt", pct(cpu.interrupt)),
+                ("idle", pct(cpu.idle)),
+            ],
+        )?
+    } else {
+        format!("{:.*}%", ctx.config.cpu.precision, busy_pct)
+    };
+    println!("{}", ctx.paint(&out, thresholds.level(busy_pct)));
+    Ok(())
+}
+
+pub fn main(args: &[String], ctx: &Context) -> Result {
+    log::debug!("Args: {:?}", args);
+    let cfg = &ctx.config.cpu;
+
+    let mut opts = Options::new();
+    opts.optflag("h", "help", "print this help menu");
+    opts.optflag("t", "test", "test cpu load aggregate and print results");
+    opts.optopt(
+        "i",
+        "interval",
+        &format!(
+            "interval length for sampling cpu (in seconds, default: {})",
+            cfg.interval
+        ),
+        "SECS",
+    );
+    toggle_opt(
+        &mut opts,
+        "s",
+        "saved",
+        "measure since the previous run instead of sleeping \
+         (samples briefly if no recent state is saved)",
+    );
+    opts.optopt(
+        "",
+        "max-age",
+        &format!(
+            "ignore saved state older than this (in seconds, default: {})",
+            cfg.max_age
+        ),
+        "SECS",
+    );
+    toggle_opt(&mut opts, "c", "cores", "show busy % of each core");
+    toggle_opt(&mut opts, "m", "max", "show busy % of the busiest core");
+    toggle_opt(&mut opts, "b", "bars", "show a bar graph with one character per core");
+    opts.optflag(
+        "",
+        "breakdown",
+        "show table of time spent in every state (iowait, steal, etc.)",
+    );
+    opts.optflag(
+        "",
+        "freq",
+        "show average clock speed (see `cpuinfo` for details)",
+    );
+    toggle_opt(&mut opts, "", "iowait", "count time waiting for I/O as busy");
+    toggle_opt(&mut opts, "", "steal", "count time stolen by the hypervisor as busy");
+    opts.optopt(
+        "f",
+        "format",
+        "format output using template; fields (in %): busy, user, nice, \
+         system, interrupt, idle (e.g., 'cpu {busy:.0}%'); with --cores, \
+         --max or --bars: max, bars, cores, count",
+        "TEMPLATE",
+    );
+    threshold_opts(&mut opts, "% busy", cfg.thresholds());
+
+    let matches = opts.parse(&args[1..])?;
+
+    if matches.opt_present("h") {
+        print_help(&args[0], opts);
+        return Ok(());
+    }
+
+    if matches.opt_present("t") {
+        cpu_test();
+    }
+
+    if matches.opt_present("freq") {
+        return super::cpuinfo::print_freq(ctx);
+    }
+
+    let thresholds = thresholds(&matches, cfg.thresholds())?;
+    let interval = matches.opt_get_default("i", cfg.interval)?;
+    let saved = toggle(&matches, "saved", cfg.saved);
+    // saved samples span however long it has been since the last call
+    let sampled = if saved { None } else { Some(interval) };
+    let max_age = Duration::from_secs(matches.opt_get_default("max-age", cfg.max_age)?);
+    let fmt = matches.opt_str("f").or_else(|| cfg.format.clone());
+    let show = (
+        toggle(&matches, "max", cfg.max),
+        toggle(&matches, "bars", cfg.bars),
+        toggle(&matches, "cores", cfg.cores),
+    );
+    if show.0 || show.1 || show.2 {
+        // per-core usage is host-wide and not collected by the daemon
+        let cores = if saved {
+            get_cpu_per_core_since_last(max_age, state::FALLBACK_SAMPLE)?
+        } else {
+            get_cpu_per_core(Duration::from_secs(interval))?
+        };
+        return print_cores(ctx, &cores, show, fmt, thresholds, sampled);
+    }
+    let iowait = toggle(&matches, "iowait", cfg.iowait);
+    let steal = toggle(&matches, "steal", cfg.steal);
+    let breakdown = matches.opt_present("breakdown");
+    if breakdown || iowait || steal {
+        // only raw counters have iowait and steal, so skip cgroup and daemon
+        let cpu = if saved {
+            get_cpu_breakdown_since_last(max_age, state::FALLBACK_SAMPLE)?
+        } else {
+            get_cpu_breakdown(Duration::from_secs(interval))?
+        };
+        let busy = cpu.busy(iowait, steal);
+        if breakdown {
+            return print_breakdown(ctx, &cpu, busy, sampled);
+        }
+        let stats = cpu.stats(iowait, steal);
+        return print_cpu(ctx, &stats, busy, None, fmt, thresholds, sampled);
+    }
+    // inside a cgroup with a cpu quota, load is relative to the quota
+    let quota = if ctx.config.host { None } else { cgroup::cpu_quota() };
+    let snapshot = ctx
+        .snapshot()
+        .filter(|s| s.cpu_quota == quota)
+        .and_then(|s| s.cpu.map(|cpu| (cpu, s.interval_secs)));
+    let (cpu, sampled) = if let Some((cpu, daemon_interval)) = snapshot {
+        (cpu, Some(daemon_interval))
+    } else if saved {
+        let cgroup = quota.and_then(|_| get_cgroup_cpu_since_last(max_age, state::FALLBACK_SAMPLE));
+        let cpu = match cgroup {
+            Some(cpu) => cpu,
+            None => get_cpu_since_last(max_age, state::FALLBACK_SAMPLE)?,
+        };
+        (cpu, None)
+    } else {
+        let sample = Duration::from_secs(interval);
+        let cpu = match quota.and_then(|_| get_cgroup_cpu(sample)) {
+            Some(cpu) => cpu,
+            None => get_cpu(sample)?,
+        };
+        (cpu, sampled)
+    };
+    print_cpu(ctx, &cpu, cpu.busy(), quota, fmt, thresholds, sampled)
+}
@@ -0,0 +1,127 @@
+/* Output cpu model and clock speed info */
+use super::{print_json, Context, Output};
+use getopts::Options;
+use log::debug;
+use serde_json::json;
+use sysinfo::{
+    cpuinfo::{get_cpu_info, CpuInfo},
+    template::{self, Value},
+    Result,
+};
+
+fn print_help(command: &str, opts: Options) {
+    let usage = format!("Usage: {} {} [options]", crate::PROG, command);
+    print!("{}", opts.usage(&usage));
+}
+
+/// Format kHz as MHz, or `-` if unknown
+fn mhz(khz: Option<u64>) -> String {
+    match khz {
+        Some(khz) => format!("{} MHz", khz / 1000),
+        None => String::from("-"),
+    }
+}
+
+fn print_info_json(info: &CpuInfo) {
+    print_json(&json!({
+        "model": info.model,
+        "cores": info.cores,
+        "threads": info.threads,
+        "freq": info.avg_freq(),
+        "min": info.min_freq(),
+        "max": info.max_freq(),
+        "governor": info.governor(),
+        "per_core": info.freqs,
+        "unit": "kHz",
+    }));
+}
+
+/// Print the average clock speed only, e.g. for `cpu --freq`
+pub fn print_freq(ctx: &Context) -> Result {
+    let info = get_cpu_info()?;
+    debug!("{:#?}", info);
+    if ctx.output == Output::Json {
+        print_info_json(&info);
+        return Ok(());
+    }
+    println!("{}", mhz(info.avg_freq()));
+    Ok(())
+}
+
+pub fn main(args: &[String], ctx: &Context) -> Result {
+    debug!("Args: {:?}", args);
+
+    let mut opts = Options::new();
+    opts.optflag("h", "help", "print this help menu");
+    opts.optflag("c", "cores", "show clock speed of each core");
+    opts.optopt(
+        "f",
+        "format",
+        "format output using template; fields: model, cores, threads, governor, \
+         freq, min, max (in MHz) (e.g., '{freq:.0} MHz {governor}')",
+        "TEMPLATE",
+    );
+
+    let matches = opts.parse(&args[1..])?;
+
+    if matches.opt_present("h") {
+        print_help(&args[0], opts);
+        return Ok(());
+    }
+
+    let info = get_cpu_info()?;
+    debug!("{:#?}", info);
+    if ctx.output == Output::Json {
+        print_info_json(&info);
+        return Ok(());
+    }
+
+    if let Some(fmt) = matches.opt_str("f") {
+        let freq = |khz: Option<u64>| Value::Float(khz.unwrap_or(0) as f64 / 1000.0);
+        let text = |s: Option<&str>| Value::Text(s.unwrap_or("-").to_string());
+        let out = template::render(
+            &fmt,
+            &[
+                ("model", text(info.model.as_deref())),
+                ("cores", Value::Int(info.cores as u64)),
+                ("threads", Value::Int(info.threads as u64)),
+                ("governor", text(info.governor())),
+                ("freq", freq(info.avg_freq())),
+                ("min", freq(info.min_freq())),
+                ("max", freq(info.max_freq())),
+            ],
+        )?;
+        println!("{}", out);
+        return Ok(());
+    }
+
+    let mut rows = vec![
+        (String::from("model"), info.model.clone().unwrap_or_default()),
+        (String::from("cores"), info.cores.to_string()),
+        (String::from("threads"), info.threads.to_string()),
+        (String::from("freq"), mhz(info.avg_freq())),
+        (String::from("min"), mhz(info.min_freq())),
+        (String::from("max"), mhz(info.max_freq())),
+        (
+            String::from("governor"),
+            info.governor().unwrap_or("-").to_string(),
+        ),
+    ];
+    if matches.opt_present("c") {
+        rows.extend(info.freqs.iter().map(|f| {
+            let mut value = mhz(f.current);
+            if let (Some(min), Some(max)) = (f.min, f.max) {
+                value += &format!(" ({}-{} MHz)", min / 1000, max / 1000);
+            }
+            if let Some(governor) = &f.governor {
+                value += &format!(" {}", governor);
+            }
+            (format!("cpu{}", f.cpu), value)
+        }));
+    }
+    let width = rows.iter().map(|(label, _)| label.len()).max().unwrap_or(0);
+    for (label, value) in rows {
+        println!("{:width$}  {}", label, value, width = width);
+    }
+    Ok(())
+}
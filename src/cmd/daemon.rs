@@ -0,0 +1,37 @@
+/* Run metric collector in the foreground */
+use super::Context;
+use getopts::Options;
+use std::time::Duration;
+use sysinfo::{daemon, Result};
+
+fn print_help(command: &str, opts: Options) {
+    const DESC: &str = "Sample all metrics in the background and serve them to other \
+                        commands over a unix socket.";
+    let usage = format!("Usage: {} {} [options]\n\n{}", crate::PROG, command, DESC);
+    print!("{}", opts.usage(&usage));
+}
+
+pub fn main(args: &[String], ctx: &Context) -> Result {
+    log::debug!("Args: {:?}", args);
+
+    let mut opts = Options::new();
+    opts.optflag("h", "help", "print this help menu");
+    opts.optopt(
+        "i",
+        "interval",
+        &format!(
+            "how often to sample metrics (in seconds, at least 1, default: {})",
+            ctx.config.daemon.interval
+        ),
+        "SECS",
+    );
+
+    let matches = opts.parse(&args[1..])?;
+    if matches.opt_present("h") {
+        print_help(&args[0], opts);
+        return Ok(());
+    }
+
+    let interval = Duration::from_secs(matches.opt_get_default("i", ctx.config.daemon.interval)?);
+    daemon::run(interval, ctx.config.host)
+}
@@ -0,0 +1,113 @@
+/* Output filesystem usage info */
+use super::{print_json, threshold_opts, thresholds, toggle, toggle_opt, Context, Output};
+use getopts::Options;
+use log::debug;
+use sysinfo::{
+    disk::{get_disk, get_disks},
+    template::{self, Value},
+    util::humanize_bytes,
+    Result,
+};
+
+fn print_help(command: &str, opts: Options) {
+    const DESC: &str = "Print usage of the filesystems containing each PATH (default: /).";
+    let usage = format!(
+        "Usage: {} {} [options] [PATH...]\n\n{}",
+        crate::PROG,
+        command,
+        DESC
+    );
+    print!("{}", opts.usage(&usage));
+}
+
+pub fn main(args: &[String], ctx: &Context) -> Result {
+    debug!("Args: {:?}", args);
+    let cfg = &ctx.config.disk;
+
+    let mut opts = Options::new();
+    opts.optflag("h", "help", "print this help menu");
+    opts.optflag("a", "all", "show all mounted filesystems (except pseudo fs)");
+    toggle_opt(&mut opts, "p", "percent", "used space as pct of total space");
+    toggle_opt(&mut opts, "u", "used", "show used space only");
+    opts.optopt(
+        "f",
+        "format",
+        "format output using template; fields: mount, device, fs, total, \
+         used, avail, pct (e.g., '{mount} {used:h}/{total:h}')",
+        "TEMPLATE",
+    );
+    threshold_opts(&mut opts, "% used", cfg.thresholds());
+
+    let matches = opts.parse(&args[1..])?;
+
+    if matches.opt_present("h") {
+        print_help(&args[0], opts);
+        return Ok(());
+    }
+
+    let used_pct = toggle(&matches, "percent", cfg.percent);
+    let used_only = toggle(&matches, "used", cfg.used);
+    let thresholds = thresholds(&matches, cfg.thresholds())?;
+
+    let disks = if matches.opt_present("a") {
+        get_disks()?
+    } else if !matches.free.is_empty() {
+        matches.free.iter().map(get_disk).collect::<Result<_>>()?
+    } else {
+        cfg.paths.iter().map(get_disk).collect::<Result<_>>()?
+    };
+    debug!("Disks: {:#?}", disks);
+
+    if ctx.output == Output::Json {
+        let disks = disks
+            .iter()
+            .map(|disk| {
+                serde_json::json!({
+                    "mount": disk.mount,
+                    "device": disk.device,
+                    "fs_type": disk.fs_type,
+                    "total": disk.total,
+                    "used": disk.used,
+                    "avail": disk.avail,
+                    "unit": "bytes",
+                    "used_pct": disk.used_pct(),
+                })
+            })
+            .collect();
+        print_json(&serde_json::Value::Array(disks));
+        return Ok(());
+    }
+
+    let fmt = matches.opt_str("f").or_else(|| cfg.format.clone());
+    let mut out = Vec::with_capacity(disks.len());
+    for disk in disks {
+        let text = if let Some(fmt) = &fmt {
+            template::render(
+                fmt,
+                &[
+                    ("mount", Value::Text(disk.mount.clone())),
+                    ("device", Value::Text(disk.device.clone())),
+                    ("fs", Value::Text(disk.fs_type.clone())),
+                    ("total", Value::Int(disk.total)),
+                    ("used", Value::Int(disk.used)),
+                    ("avail", Value::Int(disk.avail)),
+                    ("pct", Value::Float(disk.used_pct().into())),
+                ],
+            )?
+        } else if used_pct {
+            format!("{:.*}%", cfg.precision, disk.used_pct())
+        } else if used_only {
+            humanize_bytes(disk.used as f64, true, false)?
+        } else {
+            format!(
+                "{}/{}",
+                humanize_bytes(disk.used as f64, true, false)?,
+                humanize_bytes(disk.total as f64, true, false)?
+            )
+        };
+        out.push(ctx.paint(&text, thresholds.level(disk.used_pct().into())));
+    }
+    println!("{}", out.join(" "));
+
+    Ok(())
+}
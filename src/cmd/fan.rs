@@ -0,0 +1,111 @@
+/* Output fan speed info */
+use super::{print_json, Context, Output};
+use getopts::Options;
+use log::debug;
+use serde_json::json;
+use sysinfo::{
+    fan::{get_fan_from, get_fans_from},
+    hwmon::HWMON,
+    template::{self, Value},
+    Result,
+};
+
+/// Exit code when the machine has no fans, to tell it apart from errors
+const NO_FANS: i32 = 2;
+
+fn print_help(command: &str, opts: Options) {
+    let usage = format!("Usage: {} {} [options] [NAME]", crate::PROG, command);
+    print!("{}", opts.usage(&usage));
+}
+
+fn no_fans() -> ! {
+    eprintln!("no fans found");
+    std::process::exit(NO_FANS);
+}
+
+pub fn main(args: &[String], ctx: &Context) -> Result {
+    debug!("Args: {:?}", args);
+    let cfg = &ctx.config.fan;
+
+    let mut opts = Options::new();
+    opts.optflag("h", "help", "print this help menu");
+    opts.optflag("l", "list", "list all fans with their labels");
+    opts.optopt(
+        "",
+        "root",
+        "read hwmon chips from DIR instead of /sys/class/hwmon",
+        "DIR",
+    );
+    opts.optopt(
+        "f",
+        "format",
+        "format output using template; fields: name, label, rpm \
+         (e.g., '{label} {rpm}rpm')",
+        "TEMPLATE",
+    );
+
+    let matches = opts.parse(&args[1..])?;
+
+    if matches.opt_present("h") {
+        print_help(&args[0], opts);
+        return Ok(());
+    }
+
+    let root = matches
+        .opt_str("root")
+        .unwrap_or_else(|| String::from(HWMON));
+    if matches.opt_present("l") {
+        let fans = get_fans_from(&root);
+        if fans.is_empty() {
+            no_fans();
+        }
+        if ctx.output == Output::Json {
+            print_json(&json!({ "fans": fans, "unit": "rpm" }));
+            return Ok(());
+        }
+        let name_width = fans.iter().map(|f| f.name.len()).max().unwrap_or(0);
+        let label_width = fans.iter().map(|f| f.label.len()).max().unwrap_or(0);
+        for fan in fans {
+            println!(
+                "{:name_width$}  {:label_width$}  {:>5} RPM",
+                fan.name,
+                fan.label,
+                fan.rpm,
+                name_width = name_width,
+                label_width = label_width,
+            );
+        }
+        return Ok(());
+    }
+
+    let name = matches.free.first().or(cfg.name.as_ref());
+    let fan = match get_fan_from(&root, name.map(String::as_str))? {
+        Some(fan) => fan,
+        None => no_fans(),
+    };
+
+    if ctx.output == Output::Json {
+        print_json(&json!({
+            "name": fan.name,
+            "label": fan.label,
+            "rpm": fan.rpm,
+            "unit": "rpm",
+        }));
+        return Ok(());
+    }
+
+    let out = if let Some(fmt) = matches.opt_str("f").or_else(|| cfg.format.clone()) {
+        template::render(
+            &fmt,
+            &[
+                ("name", Value::Text(fan.name.clone())),
+                ("label", Value::Text(fan.label.clone())),
+                ("rpm", Value::Int(fan.rpm)),
+            ],
+        )?
+    } else {
+        format!("{} RPM", fan.rpm)
+    };
+    println!("{}", out);
+    Ok(())
+}
@@ -0,0 +1,218 @@
+/* Output system load info */
+use super::{print_json, threshold_opts, thresholds, toggle, toggle_opt, Context, Output};
+use getopts::{Matches, Options};
+use log::debug;
+use serde_json::json;
+use std::io::{Error, ErrorKind};
+use sysinfo::{
+    config::LoadConfig,
+    load::{get_load, get_tasks, online_cpus},
+    template::{self, Value},
+    Result,
+};
+
+fn print_help(command: &str, opts: Options) {
+    let usage = format!("Usage: {} {} [options]", crate::PROG, command);
+    print!("{}", opts.usage(&usage));
+}
+
+/// Averages to show, in minutes, from `--windows` or `--number`
+fn windows(matches: &Matches, cfg: &LoadConfig) -> Result<Vec<u32>> {
+    let invalid = |msg: String| Box::new(Error::new(ErrorKind::InvalidInput, msg));
+    let window = |window: &str| match window.trim().parse::<u32>() {
+        Ok(minutes @ (1 | 5 | 15)) => Ok(minutes),
+        _ => Err(invalid(format!(
+            "invalid load window `{}` (expected 1, 5 or 15)",
+            window
+        ))),
+    };
+    let list = matches.opt_str("w").or_else(|| matches.opt_str("window"));
+    if list.is_some() && matches.opt_present("n") {
+        return Err(invalid(String::from(
+            "--number and --windows cannot be combined",
+        )));
+    }
+    if let Some(list) = list {
+        return Ok(list.split(',').map(window).collect::<std::result::Result<_, _>>()?);
+    }
+    if let (None, Some(windows)) = (matches.opt_str("n"), &cfg.windows) {
+        return Ok(windows
+            .iter()
+            .map(|minutes| window(&minutes.to_string()))
+            .collect::<std::result::Result<_, _>>()?);
+    }
+    let number = matches
+        .opt_str("n")
+        .unwrap_or_else(|| cfg.number.to_string());
+    match number.parse::<usize>() {
+        Ok(n @ 1..=3) => Ok([1, 5, 15][..n].to_vec()),
+        _ => Err(invalid(format!(
+            "invalid number of load averages `{}` (expected 1, 2 or 3)",
+            number
+        ))),
+    }
+}
+
+pub fn main(args: &[String], ctx: &Context) -> Result {
+    debug!("Args: {:?}", args);
+    let cfg = &ctx.config.load;
+
+    let mut opts = Options::new();
+    opts.optflag("h", "help", "print this help menu");
+    opts.optopt(
+        "n",
+        "number",
+        &format!(
+            "number of load averages to show (1-3, default: {})",
+            cfg.number
+        ),
+        "NUMBER",
+    );
+    opts.optopt(
+        "w",
+        "windows",
+        "comma-separated averages to show, in minutes (e.g., 1,15); \
+         also limits json output",
+        "LIST",
+    );
+    opts.optopt("", "window", "same as --windows", "LIST");
+    opts.optopt(
+        "p",
+        "precision",
+        &format!("decimal places to show (default: {})", cfg.precision),
+        "N",
+    );
+    opts.optopt(
+        "s",
+        "separator",
+        "text between averages (default: space)",
+        "SEP",
+    );
+    opts.optopt(
+        "f",
+        "format",
+        "format output using template; fields: one, five, fifteen, cpus, \
+         trend, running, tasks (e.g., '{trend}{one:.2} {fifteen:.2}')",
+        "TEMPLATE",
+    );
+    toggle_opt(&mut opts, "N", "normalize", "divide load by the number of online cpus");
+    toggle_opt(
+        &mut opts,
+        "P",
+        "percent",
+        "show normalized load as a percentage (implies --normalize)",
+    );
+    toggle_opt(
+        &mut opts,
+        "t",
+        "trend",
+        "prefix with ↑ (rising), ↓ (falling) or → (stable), comparing \
+         1- to 15-minute load",
+    );
+    toggle_opt(&mut opts, "T", "tasks", "append runnable/total task counts");
+    threshold_opts(
+        &mut opts,
+        "the first load average shown, per cpu with --normalize or \
+         percent with --percent",
+        cfg.thresholds(online_cpus(), cfg.normalize, cfg.percent),
+    );
+
+    let matches = opts.parse(&args[1..])?;
+
+    if matches.opt_present("h") {
+        print_help(&args[0], opts);
+        return Ok(());
+    }
+
+    let percent = toggle(&matches, "percent", cfg.percent);
+    let normalize = percent || toggle(&matches, "normalize", cfg.normalize);
+    let mut loadavg = match ctx.snapshot().and_then(|s| s.load) {
+        Some(loadavg) => loadavg,
+        None => get_load()?,
+    };
+    let trend = loadavg.trend();
+    let show_tasks = toggle(&matches, "tasks", cfg.tasks);
+    let fmt = matches.opt_str("f").or_else(|| cfg.format.clone());
+    let tasks = if show_tasks {
+        Some(get_tasks()?)
+    } else {
+        // optional in json and templates, e.g. on macos
+        get_tasks().ok()
+    };
+    let cpus = online_cpus();
+    let defaults = cfg.thresholds(cpus, normalize, percent);
+    if normalize {
+        loadavg = loadavg.normalized(cpus);
+        if percent {
+            loadavg.one *= 100.0;
+            loadavg.five *= 100.0;
+            loadavg.fifteen *= 100.0;
+        }
+    }
+    let thresholds = thresholds(&matches, defaults)?;
+    // validated even where unused, so bad values never pass silently
+    let windows = windows(&matches, cfg)?;
+
+    if ctx.output == Output::Json {
+        let unit = match (normalize, percent) {
+            (_, true) => "percent",
+            (true, false) => "load per cpu",
+            (false, false) => "load",
+        };
+        let mut out = json!({
+            "cpus": cpus,
+            "unit": unit,
+            "trend": trend,
+            "running": tasks.map(|t| t.running),
+            "tasks": tasks.map(|t| t.total),
+        });
+        // only the selected averages
+        for (minutes, key) in [(1, "one"), (5, "five"), (15, "fifteen")].iter() {
+            if windows.contains(minutes) {
+                out[*key] = json!(loadavg.window(*minutes));
+            }
+        }
+        print_json(&out);
+        return Ok(());
+    }
+
+    let out = if let Some(fmt) = fmt {
+        template::render(
+            &fmt,
+            &[
+                ("one", Value::Float(loadavg.one.into())),
+                ("five", Value::Float(loadavg.five.into())),
+                ("fifteen", Value::Float(loadavg.fifteen.into())),
+                ("cpus", Value::Int(cpus as u64)),
+                ("trend", Value::Text(trend.arrow().to_string())),
+                ("running", Value::Int(tasks.map_or(0, |t| t.running))),
+                ("tasks", Value::Int(tasks.map_or(0, |t| t.total))),
+            ],
+        )?
+    } else {
+        let p = matches.opt_get_default("p", cfg.precision)?;
+        let separator = matches
+            .opt_str("s")
+            .unwrap_or_else(|| cfg.separator.clone());
+        let suffix = if percent { "%" } else { "" };
+        let averages = windows
+            .iter()
+            .filter_map(|&minutes| loadavg.window(minutes))
+            .map(|load| format!("{:.*}{}", p, load, suffix))
+            .collect::<Vec<_>>()
+            .join(&separator);
+        let mut out = averages;
+        if toggle(&matches, "trend", cfg.trend) {
+            out = format!("{}{}", trend.arrow(), out);
+        }
+        if let Some(tasks) = tasks.filter(|_| show_tasks) {
+            out = format!("{} {}/{}", out, tasks.running, tasks.total);
+        }
+        out
+    };
+    // colour by the first average shown
+    let first = windows.first().and_then(|&minutes| loadavg.window(minutes));
+    let level = thresholds.level(first.unwrap_or(loadavg.one).into());
+    println!("{}", ctx.paint(&out, level));
+    Ok(())
+}
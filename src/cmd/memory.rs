@@ -0,0 +1,163 @@
+/* Output memory usage info */
+use super::{print_json, threshold_opts, thresholds, toggle, toggle_opt, Context, Output};
+use getopts::Options;
+use log::debug;
+use serde_json::json;
+use sysinfo::{
+    cgroup,
+    memory::{get_memory_breakdown, get_memory_with, get_swap, Accounting},
+    template::{self, Value},
+    util::humanize_bytes,
+    Result,
+};
+
+fn print_help(command: &str, opts: Options) {
+    let usage = format!("Usage: {} {} [options]", crate::PROG, command);
+    print!("{}", opts.usage(&usage));
+}
+
+/// Print every field of the memory breakdown
+fn print_breakdown(ctx: &Context, method: Accounting) -> Result {
+    let breakdown = get_memory_breakdown(method)?;
+    debug!("{:#?}", breakdown);
+    let rows = breakdown.rows();
+
+    if ctx.output == Output::Json {
+        let mut obj = serde_json::Map::new();
+        for (label, value) in rows {
+            obj.insert(label.to_string(), json!(value));
+        }
+        obj.insert(String::from("unit"), json!("bytes"));
+        print_json(&serde_json::Value::Object(obj));
+        return Ok(());
+    }
+
+    let width = rows.iter().map(|(label, _)| label.len()).max().unwrap_or(0);
+    for (label, value) in rows {
+        let value = match value {
+            Some(n) => humanize_bytes(n as f64, true, false)?,
+            None => String::from("-"),
+        };
+        println!("{:width$}  {:>8}", label, value, width = width);
+    }
+    Ok(())
+}
+
+pub fn main(args: &[String], ctx: &Context) -> Result {
+    debug!("Args: {:?}", args);
+    let cfg = &ctx.config.memory;
+
+    let mut opts = Options::new();
+    opts.optflag("h", "help", "print this help menu");
+    toggle_opt(&mut opts, "p", "percent", "used mem as pct of total mem");
+    toggle_opt(&mut opts, "u", "used", "show used memory only");
+    toggle_opt(&mut opts, "s", "swap", "show swap instead of RAM");
+    toggle_opt(&mut opts, "c", "combined", "show RAM and swap combined");
+    opts.optflag("b", "breakdown", "show table of detailed RAM usage");
+    opts.optopt(
+        "m",
+        "method",
+        &format!(
+            "how used RAM is calculated: htop, available (total - available), \
+             free (like `free`) (default: {})",
+            cfg.method
+        ),
+        "METHOD",
+    );
+    opts.optopt(
+        "f",
+        "format",
+        "format output using template; fields: total, used, free, pct \
+         (e.g., '{used:h}/{total:h} ({pct:.0}%)')",
+        "TEMPLATE",
+    );
+    threshold_opts(&mut opts, "% used", cfg.thresholds());
+
+    let matches = opts.parse(&args[1..])?;
+
+    if matches.opt_present("h") {
+        print_help(&args[0], opts);
+        return Ok(());
+    }
+
+    let used_pct = toggle(&matches, "percent", cfg.percent);
+    debug!("Opt: Used mem as pct: {}", used_pct);
+    let used_only = toggle(&matches, "used", cfg.used);
+    debug!("Opt: Used mem only: {}", used_only);
+    let swap = toggle(&matches, "swap", cfg.swap);
+    let combined = toggle(&matches, "combined", cfg.combined);
+    let method = matches.opt_get_default("m", cfg.method)?;
+    debug!("Opt: Accounting method: {}", method);
+    let thresholds = thresholds(&matches, cfg.thresholds())?;
+
+    if matches.opt_present("b") {
+        return print_breakdown(ctx, method);
+    }
+
+    // inside a cgroup with a memory limit, ram is relative to the limit
+    let cgroup = if ctx.config.host { None } else { cgroup::memory() };
+    // the daemon only collects memory using the default method
+    let ram = || -> Result<_> {
+        if let Some(stats) = cgroup {
+            debug!("Using cgroup memory limit");
+            return Ok(stats);
+        }
+        match ctx
+            .snapshot()
+            .filter(|_| method == Accounting::default())
+            .and_then(|s| s.memory)
+        {
+            Some(stats) => Ok(stats),
+            None => get_memory_with(method),
+        }
+    };
+    let (kind, stats) = if combined {
+        ("combined", ram()?.combine(&get_swap()?))
+    } else if swap {
+        ("swap", get_swap()?)
+    } else {
+        ("ram", ram()?)
+    };
+
+    debug!("Used: {} ({:.2}%)", stats.used, stats.used_pct());
+
+    // Write output
+    if ctx.output == Output::Json {
+        print_json(&json!({
+            "total": stats.total,
+            "used": stats.used,
+            "unit": "bytes",
+            "used_pct": stats.used_pct(),
+            "kind": kind,
+            "cgroup_limit": cgroup.filter(|_| kind == "ram").map(|c| c.total),
+        }));
+        return Ok(());
+    }
+
+    let out = if let Some(fmt) = matches.opt_str("f").or_else(|| cfg.format.clone()) {
+        template::render(
+            &fmt,
+            &[
+                ("total", Value::Int(stats.total as u64)),
+                ("used", Value::Int(stats.used as u64)),
+                ("free", Value::Int(stats.total.saturating_sub(stats.used) as u64)),
+                ("pct", Value::Float(stats.used_pct().into())),
+            ],
+        )?
+    } else if used_pct {
+        format!("{:.*}%", cfg.precision, stats.used_pct())
+    } else if used_only {
+        humanize_bytes(stats.used as f64, true, false)?
+    } else {
+        format!(
+            "{}/{}",
+            humanize_bytes(stats.used as f64, true, false)?,
+            humanize_bytes(stats.total as f64, true, false)?
+        )
+    };
+
+    let level = thresholds.level(stats.used_pct().into());
+    println!("{}", ctx.paint(&out, level));
+
+    Ok(())
+}
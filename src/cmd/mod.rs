@@ -0,0 +1,112 @@
+/* Subcommands of the sysinfo binary */
+use getopts::{Matches, Options};
+use log::debug;
+use std::path::PathBuf;
+use sysinfo::{
+    color::{self, Level, Thresholds},
+    config::Config,
+    daemon::{query, Snapshot},
+    Result,
+};
+
+pub mod battery;
+pub mod config;
+pub mod cpu;
+pub mod cpuinfo;
+pub mod daemon;
+pub mod disk;
+pub mod fan;
+pub mod load;
+pub mod memory;
+pub mod net;
+pub mod temp;
+pub mod uptime;
+
+/// How a command should write its result
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Output {
+    /// Compact string for status lines
+    Text,
+    /// Structured object with raw values and units
+    Json,
+}
+
+/// Options that apply to every command
+#[derive(Debug)]
+pub struct Context {
+    pub output:      Output,
+    /// Config file merged with global flags; commands apply their own flags
+    pub config:      Config,
+    /// File `config` was loaded from, if any
+    pub config_path: Option<PathBuf>,
+}
+
+impl Context {
+    /// Colour `text` according to `level` in the selected style
+    pub fn paint(&self, text: &str, level: Level) -> String {
+        color::paint(text, level, self.config.color)
+    }
+
+    /// Latest metrics from the daemon, if enabled and running
+    pub fn snapshot(&self) -> Option<Snapshot> {
+        if !self.config.use_daemon {
+            return None;
+        }
+        query()
+            .map_err(|e| debug!("Daemon not available: {}", e))
+            .ok()
+    }
+}
+
+/// Print json value on a single line
+pub fn print_json(value: &serde_json::Value) {
+    println!("{}", value);
+}
+
+/// Add the `--warn`/`--crit` threshold options
+pub fn threshold_opts(opts: &mut Options, unit: &str, default: Thresholds) {
+    opts.optopt(
+        "",
+        "warn",
+        &format!("warning threshold in {} (default: {})", unit, default.warning),
+        "VALUE",
+    );
+    opts.optopt(
+        "",
+        "crit",
+        &format!("critical threshold in {} (default: {})", unit, default.critical),
+        "VALUE",
+    );
+}
+
+/// Add a flag as `--<long>` plus `--no-<long>` to turn it off again
+///
+/// Booleans enabled in the config file can only be disabled this way.
+pub fn toggle_opt(opts: &mut Options, short: &str, long: &str, desc: &str) {
+    // repeatable, so that a later flag can undo one from a shell alias
+    opts.optflagmulti(short, long, desc);
+    opts.optflagmulti(
+        "",
+        &format!("no-{}", long),
+        &format!("turn off --{}, e.g. if enabled in the config", long),
+    );
+}
+
+/// Get a flag added by `toggle_opt`, or `default` if neither form is given
+///
+/// If both forms are given, the last one wins.
+pub fn toggle(matches: &Matches, long: &str, default: bool) -> bool {
+    let last = |name: &str| matches.opt_positions(name).into_iter().max();
+    match (last(long), last(&format!("no-{}", long))) {
+        (None, None) => default,
+        (on, off) => on > off,
+    }
+}
+
+/// Get thresholds from parsed `--warn`/`--crit` options
+pub fn thresholds(matches: &Matches, default: Thresholds) -> Result<Thresholds> {
+    Ok(Thresholds::new(
+        matches.opt_get_default("warn", default.warning)?,
+        matches.opt_get_default("crit", default.critical)?,
+    ))
+}
@@ -0,0 +1,152 @@
+/* Output network throughput */
+use super::{print_json, toggle, toggle_opt, Context, Output};
+use getopts::Options;
+use log::debug;
+use serde_json::json;
+use std::time::Duration;
+use sysinfo::{
+    net::{default_interface, get_net, get_net_since_last},
+    state,
+    template::{self, Value},
+    util::humanize_bytes_with,
+    Result,
+};
+
+/// Human-readable bytes without trailing zeros, e.g. `340k` rather than `340.0k`
+fn compact(bytes: f64, precision: usize) -> Result<String> {
+    let out = humanize_bytes_with(bytes, true, false, precision)?;
+    let (num, unit) = out.split_at(out.trim_end_matches(char::is_alphabetic).len());
+    let num = if num.contains('.') {
+        num.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        num
+    };
+    Ok(format!("{}{}", num, unit))
+}
+
+fn print_help(command: &str, opts: Options) {
+    const DESC: &str = "Print bytes/sec received (↓) and sent (↑) on IFACE \
+                        (default: interface of the default route).";
+    let usage = format!(
+        "Usage: {} {} [options] [IFACE]\n\n{}",
+        crate::PROG,
+        command,
+        DESC
+    );
+    print!("{}", opts.usage(&usage));
+}
+
+pub fn main(args: &[String], ctx: &Context) -> Result {
+    debug!("Args: {:?}", args);
+    let cfg = &ctx.config.net;
+
+    let mut opts = Options::new();
+    opts.optflag("h", "help", "print this help menu");
+    opts.optopt(
+        "i",
+        "interval",
+        &format!(
+            "interval length for sampling (in seconds, default: {})",
+            cfg.interval
+        ),
+        "SECS",
+    );
+    toggle_opt(
+        &mut opts,
+        "s",
+        "saved",
+        "measure since the previous run instead of sleeping \
+         (samples briefly if no recent state is saved)",
+    );
+    opts.optopt(
+        "",
+        "max-age",
+        &format!(
+            "ignore saved state older than this (in seconds, default: {})",
+            cfg.max_age
+        ),
+        "SECS",
+    );
+    toggle_opt(&mut opts, "t", "total", "also show bytes transferred since boot");
+    opts.optopt(
+        "p",
+        "precision",
+        &format!(
+            "most decimal places to show (default: {})",
+            cfg.precision
+        ),
+        "N",
+    );
+    opts.optopt(
+        "f",
+        "format",
+        "format output using template; fields: iface, rx, tx (bytes/sec), \
+         rx_total, tx_total (e.g., '↓{rx:h} ↑{tx:h}')",
+        "TEMPLATE",
+    );
+
+    let matches = opts.parse(&args[1..])?;
+
+    if matches.opt_present("h") {
+        print_help(&args[0], opts);
+        return Ok(());
+    }
+
+    let interface = match matches.free.first().or(cfg.interface.as_ref()) {
+        Some(interface) => interface.clone(),
+        None => default_interface()?,
+    };
+    debug!("Interface: {}", interface);
+
+    let stats = if toggle(&matches, "saved", cfg.saved) {
+        let max_age = Duration::from_secs(matches.opt_get_default("max-age", cfg.max_age)?);
+        get_net_since_last(&interface, max_age, state::FALLBACK_SAMPLE)?
+    } else {
+        let interval = matches.opt_get_default("i", cfg.interval)?;
+        get_net(&interface, Duration::from_secs(interval))?
+    };
+
+    if ctx.output == Output::Json {
+        print_json(&json!({
+            "interface": stats.interface,
+            "rx_rate": stats.rx_rate,
+            "tx_rate": stats.tx_rate,
+            "rate_unit": "bytes/sec",
+            "rx_total": stats.rx_total,
+            "tx_total": stats.tx_total,
+            "total_unit": "bytes",
+        }));
+        return Ok(());
+    }
+
+    let out = if let Some(fmt) = matches.opt_str("f").or_else(|| cfg.format.clone()) {
+        template::render(
+            &fmt,
+            &[
+                ("iface", Value::Text(stats.interface.clone())),
+                ("rx", Value::Float(stats.rx_rate)),
+                ("tx", Value::Float(stats.tx_rate)),
+                ("rx_total", Value::Int(stats.rx_total)),
+                ("tx_total", Value::Int(stats.tx_total)),
+            ],
+        )?
+    } else {
+        let p = matches.opt_get_default("p", cfg.precision)?;
+        let mut out = format!(
+            "↓{} ↑{}",
+            compact(stats.rx_rate, p)?,
+            compact(stats.tx_rate, p)?
+        );
+        if toggle(&matches, "total", cfg.total) {
+            out.push_str(&format!(
+                " (↓{} ↑{})",
+                compact(stats.rx_total as f64, p)?,
+                compact(stats.tx_total as f64, p)?
+            ));
+        }
+        out
+    };
+    println!("{}", out);
+
+    Ok(())
+}
@@ -0,0 +1,156 @@
+/* Output cpu info */
+use super::{print_json, threshold_opts, thresholds, toggle, toggle_opt, Context, Output};
+use getopts::Options;
+use serde_json::json;
+use sysinfo::{
+    temp::{get_sensor, get_sensors, get_temp_with, Aggregate, Unit},
+    template::{self, Value},
+    Result,
+};
+
+fn print_help(command: &str, opts: Options) {
+    let usage = format!("Usage: {} {} [options]", crate::PROG, command);
+    print!("{}", opts.usage(&usage));
+}
+
+/// Print a table of all sensors
+fn print_sensors(ctx: &Context, unit: Unit, precision: usize, symbol: &str) -> Result {
+    let sensors = get_sensors();
+    if ctx.output == Output::Json {
+        print_json(&json!({ "sensors": sensors, "unit": "celsius" }));
+        return Ok(());
+    }
+
+    let name_width = sensors.iter().map(|s| s.name.len()).max().unwrap_or(0);
+    let label_width = sensors.iter().map(|s| s.label.len()).max().unwrap_or(0);
+    for sensor in sensors {
+        let value = format!("{:.*}{}", precision, sensor.temp().value(unit), symbol);
+        println!(
+            "{:name_width$}  {:label_width$}  {:>7}{}",
+            sensor.name,
+            sensor.label,
+            value,
+            if sensor.cpu { "  cpu" } else { "" },
+            name_width = name_width,
+            label_width = label_width,
+        );
+    }
+    Ok(())
+}
+
+pub fn main(args: &[String], ctx: &Context) -> Result {
+    log::debug!("Args: {:?}", args);
+    let cfg = &ctx.config.temp;
+
+    let default_unit = cfg.unit.unwrap_or(if cfg.celsius {
+        Unit::Celsius
+    } else {
+        Unit::Fahrenheit
+    });
+
+    let mut opts = Options::new();
+    opts.optflag("h", "help", "print this help menu");
+    opts.optopt(
+        "u",
+        "unit",
+        &format!(
+            "show result in C (celsius), F (fahrenheit), K (kelvin) or \
+             auto (by locale) (default: {})",
+            default_unit
+        ),
+        "UNIT",
+    );
+    opts.optflag("c", "celcius", "same as --unit C");
+    toggle_opt(
+        &mut opts,
+        "",
+        "symbol",
+        "append the degree symbol (or K); on by default",
+    );
+    opts.optopt(
+        "p",
+        "precision",
+        &format!("decimal places to show (default: {})", cfg.precision),
+        "N",
+    );
+    opts.optopt(
+        "s",
+        "sensor",
+        "report sensor with this name or label (see --list)",
+        "NAME",
+    );
+    opts.optopt(
+        "a",
+        "aggregate",
+        &format!(
+            "combine cpu package sensors using max or avg (default: {})",
+            cfg.aggregate
+        ),
+        "METHOD",
+    );
+    opts.optflag("l", "list", "list all sensors with their labels");
+    opts.optopt(
+        "f",
+        "format",
+        "format output using template; fields: temp, celsius, fahrenheit, \
+         kelvin (e.g., '{temp:.0}º')",
+        "TEMPLATE",
+    );
+    threshold_opts(&mut opts, "degrees celsius", cfg.thresholds());
+
+    let matches = opts.parse(&args[1..])?;
+
+    if matches.opt_present("h") {
+        print_help(&args[0], opts);
+        return Ok(());
+    }
+
+    let unit = match matches.opt_get::<Unit>("u")? {
+        Some(unit) => unit,
+        None if matches.opt_present("c") => Unit::Celsius,
+        None => default_unit,
+    };
+    let precision = matches.opt_get_default("p", cfg.precision)?;
+    let symbol = if toggle(&matches, "symbol", cfg.symbol) {
+        unit.symbol()
+    } else {
+        ""
+    };
+    if matches.opt_present("l") {
+        return print_sensors(ctx, unit, precision, symbol);
+    }
+
+    let thresholds = thresholds(&matches, cfg.thresholds())?;
+    let aggregate = matches.opt_get_default("a", cfg.aggregate)?;
+    let temp = match matches.opt_str("s").or_else(|| cfg.sensor.clone()) {
+        Some(name) => get_sensor(&name)?.temp(),
+        None => match ctx.snapshot().and_then(|s| s.temp) {
+            // the daemon only records the default aggregation
+            Some(temp) if aggregate == Aggregate::default() => temp,
+            _ => get_temp_with(aggregate)?,
+        },
+    };
+    let value = temp.value(unit);
+
+    if ctx.output == Output::Json {
+        print_json(&json!({ "temp": value, "unit": unit.to_string() }));
+        return Ok(());
+    }
+
+    let level = thresholds.level(temp.celsius.into());
+    let out = if let Some(fmt) = matches.opt_str("f").or_else(|| cfg.format.clone()) {
+        template::render(
+            &fmt,
+            &[
+                ("temp", Value::Float(value.into())),
+                ("celsius", Value::Float(temp.celsius.into())),
+                ("fahrenheit", Value::Float(temp.fahrenheit().into())),
+                ("kelvin", Value::Float(temp.kelvin().into())),
+            ],
+        )?
+    } else {
+        format!("{:.*}{}", precision, value, symbol)
+    };
+    println!("{}", ctx.paint(&out, level));
+    Ok(())
+}
@@ -0,0 +1,157 @@
+/* Output system uptime */
+use super::{print_json, toggle, toggle_opt, Context, Output};
+use getopts::Options;
+use serde_json::json;
+use std::io::{Error, ErrorKind};
+use sysinfo::{
+    template::{self, Value},
+    uptime::{get_boot_time, get_uptime, Style},
+    Result,
+};
+
+fn print_help(command: &str, opts: Options) {
+    const DESC: &str = "Print uptime in specified format.";
+    let usage = format!("Usage: {} {} [options]\n\n{}", crate::PROG, command, DESC);
+    print!("{}", opts.usage(&usage));
+}
+
+/// Print when the system was booted, using strftime format `fmt`
+fn print_boot_time(ctx: &Context, utc: bool, fmt: &str) -> Result {
+    use chrono::format::{Item, StrftimeItems};
+
+    if StrftimeItems::new(fmt).any(|item| item == Item::Error) {
+        return Err(Box::new(Error::new(
+            ErrorKind::InvalidInput,
+            format!("invalid boot time format `{}`", fmt),
+        )));
+    }
+    let boot = get_boot_time()?;
+    if ctx.output == Output::Json {
+        print_json(&json!({ "boot_time": boot.timestamp(), "unit": "unix seconds" }));
+        return Ok(());
+    }
+    if utc {
+        println!("{}", boot.format(fmt));
+    } else {
+        println!("{}", boot.with_timezone(&chrono::Local).format(fmt));
+    }
+    Ok(())
+}
+
+pub fn main(args: &[String], ctx: &Context) -> Result {
+    log::debug!("Args: {:?}", args);
+    let cfg = &ctx.config.uptime;
+
+    let mut opts = Options::new();
+    opts.optflag("h", "help", "print this help menu");
+    toggle_opt(&mut opts, "w", "weeks", "format as weeks instead of days");
+    toggle_opt(
+        &mut opts,
+        "p",
+        "precise",
+        "show one more unit, e.g. hours after days",
+    );
+    opts.optopt(
+        "s",
+        "style",
+        &format!(
+            "compact (3d4h), long (3 days, 4 hours) or clock (3d 04:12) \
+             (default: {})",
+            cfg.style
+        ),
+        "STYLE",
+    );
+    toggle_opt(&mut opts, "S", "seconds", "also show seconds");
+    opts.optopt(
+        "",
+        "prefix",
+        &format!("text before the uptime (default: {})", cfg.prefix),
+        "TEXT",
+    );
+    opts.optopt(
+        "t",
+        "time-format",
+        "format using %w (weeks), %d (days), %D (days of week), %H, %M, %S \
+         (hours, minutes, seconds), %s (total seconds), e.g. '%dd %H:%M'",
+        "FORMAT",
+    );
+    opts.optflag("b", "boot-time", "show when the system was booted");
+    opts.optopt(
+        "",
+        "boot-format",
+        &format!(
+            "strftime format for --boot-time (default: {})",
+            cfg.boot_format
+        ),
+        "FORMAT",
+    );
+    toggle_opt(&mut opts, "u", "utc", "show boot time in UTC instead of local time");
+    opts.optopt(
+        "f",
+        "format",
+        "format output using template; fields: weeks, days, week_days, \
+         hours, minutes, seconds, total_hours, total_minutes, \
+         total_seconds (e.g., 'up {days}d {hours}h')",
+        "TEMPLATE",
+    );
+
+    let matches = opts.parse(&args[1..])?;
+    if matches.opt_present("h") {
+        print_help(&args[0], opts);
+        return Ok(());
+    }
+
+    if matches.opt_present("b") {
+        let fmt = matches
+            .opt_str("boot-format")
+            .unwrap_or_else(|| cfg.boot_format.clone());
+        return print_boot_time(ctx, toggle(&matches, "utc", cfg.utc), &fmt);
+    }
+
+    let uptime = match ctx.snapshot().and_then(|s| s.uptime) {
+        Some(uptime) => uptime,
+        None => get_uptime()?,
+    };
+    if ctx.output == Output::Json {
+        print_json(&json!({ "uptime": uptime.seconds, "unit": "seconds" }));
+        return Ok(());
+    }
+
+    if let Some(fmt) = matches.opt_str("f").or_else(|| cfg.format.clone()) {
+        let secs = uptime.seconds;
+        let (days, weeks) = (uptime.parts(false), uptime.parts(true));
+        let out = template::render(
+            &fmt,
+            &[
+                ("weeks", Value::Int(weeks.weeks)),
+                ("days", Value::Int(days.days)),
+                ("week_days", Value::Int(weeks.days)),
+                ("hours", Value::Int(days.hours)),
+                ("minutes", Value::Int(days.minutes)),
+                ("seconds", Value::Int(days.seconds)),
+                ("total_hours", Value::Int(secs / 3_600)),
+                ("total_minutes", Value::Int(secs / 60)),
+                ("total_seconds", Value::Int(secs)),
+            ],
+        )?;
+        println!("{}", out);
+        return Ok(());
+    }
+
+    let prefix = matches.opt_str("prefix").unwrap_or_else(|| cfg.prefix.clone());
+    if let Some(fmt) = matches.opt_str("t").or_else(|| cfg.time_format.clone()) {
+        println!("{}{}", prefix, uptime.strftime(&fmt)?);
+        return Ok(());
+    }
+
+    let parts = uptime.parts(toggle(&matches, "weeks", cfg.weeks));
+    let precise = toggle(&matches, "precise", cfg.precise);
+    let seconds = toggle(&matches, "seconds", cfg.seconds);
+    let out = match matches.opt_get_default("s", cfg.style)? {
+        Style::Compact => parts.compact(precise, seconds),
+        Style::Long => parts.long(precise, seconds),
+        Style::Clock => parts.clock(seconds),
+    };
+    println!("{}{}", prefix, out);
+    Ok(())
+}
@@ -0,0 +1,74 @@
+/* Threshold-based colouring of output values */
+use serde::{Deserialize, Serialize};
+use std::{
+    io::{Error, ErrorKind},
+    str::FromStr,
+};
+
+/// How colours are written to the terminal/status line
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Style {
+    None,
+    /// Tmux format strings, e.g. `#[fg=red]`
+    Tmux,
+    /// ANSI escape sequences
+    Ansi,
+}
+
+impl FromStr for Style {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Style::None),
+            "tmux" => Ok(Style::Tmux),
+            "ansi" => Ok(Style::Ansi),
+            _ => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("invalid color style `{}` (expected none, tmux or ansi)", s),
+            )),
+        }
+    }
+}
+
+/// Severity of a value relative to its thresholds
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum Level {
+    Normal,
+    Warning,
+    Critical,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Thresholds {
+    pub warning:  f64,
+    pub critical: f64,
+}
+
+impl Thresholds {
+    pub fn new(warning: f64, critical: f64) -> Thresholds {
+        Thresholds { warning, critical }
+    }
+
+    pub fn level(&self, value: f64) -> Level {
+        if value >= self.critical {
+            Level::Critical
+        } else if value >= self.warning {
+            Level::Warning
+        } else {
+            Level::Normal
+        }
+    }
+}
+
+/// Wrap `text` in the colour for `level`; normal values are left as-is
+pub fn paint(text: &str, level: Level, style: Style) -> String {
+    match (style, level) {
+        (Style::None, _) | (_, Level::Normal) => text.to_string(),
+        (Style::Tmux, Level::Warning) => format!("#[fg=yellow]{}#[default]", text),
+        (Style::Tmux, Level::Critical) => format!("#[fg=red]{}#[default]", text),
+        (Style::Ansi, Level::Warning) => format!("\x1b[33m{}\x1b[0m", text),
+        (Style::Ansi, Level::Critical) => format!("\x1b[31m{}\x1b[0m", text),
+    }
+}
@@ -0,0 +1,422 @@
+/* Load default options from a config file */
+//! Every key is optional; missing keys keep their default. Command line
+//! flags take precedence over values set here. Every flag backed by a boolean
+//! key has a negated form (e.g. `--no-percent` for `memory.percent = true`)
+//! to turn it off for one run; if both forms are given, the last one wins.
+//!
+//! ```toml
+//! color = "tmux"
+//!
+//! [memory]
+//! format = "{used:h}/{total:h}"
+//! warn = 80.0
+//!
+//! [temp]
+//! unit = "c"
+//! ```
+use crate::{
+    color::{Style, Thresholds},
+    memory::Accounting,
+    temp::{Aggregate, Unit},
+    uptime::Style as UptimeStyle,
+    Result,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Color style for values above thresholds
+    pub color:      Style,
+    /// Output json instead of text
+    pub json:       bool,
+    /// Ask a running daemon for metrics before collecting them
+    pub use_daemon: bool,
+    /// Report host-wide memory and cpu even when limited by a cgroup
+    pub host:       bool,
+    pub memory:     MemoryConfig,
+    pub battery:    BatteryConfig,
+    pub disk:       DiskConfig,
+    pub fan:        FanConfig,
+    pub cpu:        CpuConfig,
+    pub load:       LoadConfig,
+    pub net:        NetConfig,
+    pub temp:       TempConfig,
+    pub uptime:     UptimeConfig,
+    pub daemon:     DaemonConfig,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            color:      Style::None,
+            json:       false,
+            use_daemon: true,
+            host:       false,
+            memory:     MemoryConfig::default(),
+            battery:    BatteryConfig::default(),
+            disk:       DiskConfig::default(),
+            fan:        FanConfig::default(),
+            cpu:        CpuConfig::default(),
+            load:       LoadConfig::default(),
+            net:        NetConfig::default(),
+            temp:       TempConfig::default(),
+            uptime:     UptimeConfig::default(),
+            daemon:     DaemonConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MemoryConfig {
+    /// Show used mem as pct of total mem
+    pub percent:   bool,
+    /// Show used memory only
+    pub used:      bool,
+    /// Show swap instead of RAM
+    pub swap:      bool,
+    /// Show RAM and swap combined
+    pub combined:  bool,
+    /// How used RAM is calculated
+    pub method:    Accounting,
+    /// Decimal places of percentages
+    pub precision: usize,
+    pub format:    Option<String>,
+    /// Thresholds in % used
+    pub warn:      f64,
+    pub crit:      f64,
+}
+
+impl Default for MemoryConfig {
+    fn default() -> MemoryConfig {
+        MemoryConfig {
+            percent:   false,
+            used:      false,
+            swap:      false,
+            combined:  false,
+            method:    Accounting::Htop,
+            precision: 1,
+            format:    None,
+            warn:      75.0,
+            crit:      90.0,
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BatteryConfig {
+    /// Battery to report (default: first battery found)
+    pub name:   Option<String>,
+    /// Prefix output with a glyph for charge level and state
+    pub icon:   bool,
+    /// Show charging/discharging/full state
+    pub state:  bool,
+    pub format: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DiskConfig {
+    /// Paths or mount points to report when none are given
+    pub paths:     Vec<String>,
+    /// Show used space as pct of total space
+    pub percent:   bool,
+    /// Show used space only
+    pub used:      bool,
+    pub precision: usize,
+    pub format:    Option<String>,
+    /// Thresholds in % used
+    pub warn:      f64,
+    pub crit:      f64,
+}
+
+impl Default for DiskConfig {
+    fn default() -> DiskConfig {
+        DiskConfig {
+            paths:     vec![String::from("/")],
+            percent:   false,
+            used:      false,
+            precision: 1,
+            format:    None,
+            warn:      80.0,
+            crit:      90.0,
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FanConfig {
+    /// Fan name or label to report (default: fastest fan)
+    pub name:   Option<String>,
+    pub format: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CpuConfig {
+    /// Sampling interval in seconds
+    pub interval:  u64,
+    /// Measure since the previous run instead of sleeping
+    pub saved:     bool,
+    /// Ignore saved state older than this (in seconds)
+    pub max_age:   u64,
+    /// Show busy % of each core
+    pub cores:     bool,
+    /// Show busy % of the busiest core
+    pub max:       bool,
+    /// Show a bar graph with one character per core
+    pub bars:      bool,
+    /// Count time waiting for I/O as busy
+    pub iowait:    bool,
+    /// Count time stolen by the hypervisor as busy
+    pub steal:     bool,
+    pub precision: usize,
+    pub format:    Option<String>,
+    /// Thresholds in % busy
+    pub warn:      f64,
+    pub crit:      f64,
+}
+
+impl Default for CpuConfig {
+    fn default() -> CpuConfig {
+        CpuConfig {
+            interval:  1,
+            saved:     false,
+            max_age:   60,
+            cores:     false,
+            max:       false,
+            bars:      false,
+            iowait:    false,
+            steal:     false,
+            precision: 1,
+            format:    None,
+            warn:      70.0,
+            crit:      90.0,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoadConfig {
+    /// Number of load averages to show (1-3)
+    pub number:    usize,
+    /// Averages to show, in minutes (1, 5 or 15); overrides `number`
+    pub windows:   Option<Vec<u32>>,
+    /// Text between averages
+    pub separator: String,
+    /// Divide by the number of online cpus
+    pub normalize: bool,
+    /// Show normalized load as a percentage
+    pub percent:   bool,
+    /// Prefix with an arrow comparing 1- to 15-minute load
+    pub trend:     bool,
+    /// Append runnable/total task counts
+    pub tasks:     bool,
+    pub precision: usize,
+    pub format:    Option<String>,
+    /// Thresholds for the first average shown, as displayed: per cpu when
+    /// normalized and in percent with `percent` (default: 70% and 100% of
+    /// the cpus)
+    pub warn:      Option<f64>,
+    pub crit:      Option<f64>,
+}
+
+impl LoadConfig {
+    /// Thresholds in the units load is displayed in
+    ///
+    /// Only the defaults depend on `cpus`, so one config suits every machine.
+    pub fn thresholds(&self, cpus: usize, normalize: bool, percent: bool) -> Thresholds {
+        let full = match (normalize, percent) {
+            (_, true) => 100.0,
+            (true, false) => 1.0,
+            (false, false) => cpus as f64,
+        };
+        Thresholds::new(
+            self.warn.unwrap_or(0.7 * full),
+            self.crit.unwrap_or(full),
+        )
+    }
+}
+
+impl Default for LoadConfig {
+    fn default() -> LoadConfig {
+        LoadConfig {
+            number:    3,
+            windows:   None,
+            separator: String::from(" "),
+            normalize: false,
+            percent:   false,
+            trend:     false,
+            tasks:     false,
+            precision: 2,
+            format:    None,
+            warn:      None,
+            crit:      None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NetConfig {
+    /// Interface to report (default: interface of the default route)
+    pub interface: Option<String>,
+    /// Sampling interval in seconds
+    pub interval:  u64,
+    /// Measure since the previous run instead of sleeping
+    pub saved:     bool,
+    /// Ignore saved state older than this (in seconds)
+    pub max_age:   u64,
+    /// Also show bytes transferred since boot
+    pub total:     bool,
+    /// Most decimal places of rates and totals; trailing zeros are dropped
+    pub precision: usize,
+    pub format:    Option<String>,
+}
+
+impl Default for NetConfig {
+    fn default() -> NetConfig {
+        NetConfig {
+            interface: None,
+            interval:  1,
+            saved:     false,
+            max_age:   60,
+            total:     false,
+            precision: 1,
+            format:    None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TempConfig {
+    /// Unit to show: c, f, k or auto (default: fahrenheit, or celsius if
+    /// `celsius`)
+    pub unit:      Option<Unit>,
+    /// Show degrees celsius instead of fahrenheit (same as `unit = "c"`)
+    #[serde(alias = "celcius")]
+    pub celsius:   bool,
+    /// Append the degree symbol (or K)
+    pub symbol:    bool,
+    /// Sensor name or label to report (default: cpu package sensors)
+    pub sensor:    Option<String>,
+    /// How readings of several cpu package sensors are combined
+    pub aggregate: Aggregate,
+    pub precision: usize,
+    pub format:    Option<String>,
+    /// Thresholds in degrees celsius
+    pub warn:      f64,
+    pub crit:      f64,
+}
+
+impl Default for TempConfig {
+    fn default() -> TempConfig {
+        TempConfig {
+            unit:      None,
+            celsius:   false,
+            symbol:    true,
+            sensor:    None,
+            aggregate: Aggregate::Max,
+            precision: 0,
+            format:    None,
+            warn:      70.0,
+            crit:      80.0,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UptimeConfig {
+    /// compact (3d4h), long (3 days, 4 hours) or clock (3d 04:12)
+    pub style:       UptimeStyle,
+    /// Format as weeks instead of days
+    pub weeks:       bool,
+    /// Show one more unit, e.g. hours after days
+    pub precise:     bool,
+    /// Also show seconds
+    pub seconds:     bool,
+    /// Text before the uptime
+    pub prefix:      String,
+    pub format:      Option<String>,
+    /// `strftime`-like template, e.g. `%dd %H:%M`
+    pub time_format: Option<String>,
+    /// strftime format of the boot time
+    pub boot_format: String,
+    /// Show boot time in UTC instead of local time
+    pub utc:         bool,
+}
+
+impl Default for UptimeConfig {
+    fn default() -> UptimeConfig {
+        UptimeConfig {
+            style:       UptimeStyle::Compact,
+            weeks:       false,
+            precise:     false,
+            seconds:     false,
+            prefix:      String::from("↑"),
+            format:      None,
+            time_format: None,
+            boot_format: String::from("%Y-%m-%d %H:%M:%S"),
+            utc:         false,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DaemonConfig {
+    /// How often to sample metrics (in seconds)
+    pub interval: u64,
+}
+
+impl Default for DaemonConfig {
+    fn default() -> DaemonConfig {
+        DaemonConfig { interval: 2 }
+    }
+}
+
+macro_rules! impl_thresholds {
+    ($($section:ty),*) => {
+        $(impl $section {
+            pub fn thresholds(&self) -> Thresholds {
+                Thresholds::new(self.warn, self.crit)
+            }
+        })*
+    };
+}
+
+impl_thresholds!(MemoryConfig, DiskConfig, CpuConfig, TempConfig);
+
+impl Config {
+    /// `$XDG_CONFIG_HOME/sysinfo/config.toml` or `~/.config/sysinfo/config.toml`
+    pub fn default_path() -> Option<PathBuf> {
+        let dir = match env::var_os("XDG_CONFIG_HOME") {
+            Some(dir) => PathBuf::from(dir),
+            None => PathBuf::from(env::var_os("HOME")?).join(".config"),
+        };
+        Some(dir.join("sysinfo").join("config.toml"))
+    }
+
+    /// Read config from `path`
+    pub fn load(path: &Path) -> Result<Config> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("error reading config {:?}: {}", path, e))?;
+        Ok(toml::from_str(&contents)
+            .map_err(|e| format!("error parsing config {:?}: {}", path, e))?)
+    }
+
+    /// Serialize as toml, e.g. to show the effective config
+    pub fn to_toml(&self) -> Result<String> {
+        Ok(toml::to_string(self)?)
+    }
+}
@@ -1,95 +1,557 @@
-/* Output cpu info */
-use getopts::Options;
+/* Collect cpu usage info */
+use crate::{
+    state::{self, Sample},
+    Result,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    io::{Error, ErrorKind},
+    time::Duration,
+};
 
-fn print_help(command: &str, opts: Options) {
-    let usage = format!("Usage: {} {} [options]", super::PROG, command);
-    print!("{}", opts.usage(&usage));
+/// Fraction of cpu time spent in each state over a sampling interval
+///
+/// Time stolen by the hypervisor and time waiting for I/O count as idle.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct CpuStats {
+    pub user:      f32,
+    pub nice:      f32,
+    pub system:    f32,
+    pub interrupt: f32,
+    pub idle:      f32,
 }
 
-#[cfg(target_os = "linux")]
-fn cpu_test() {
-    use std::thread;
-    use systemstat::{Duration, Platform, System};
-
-    let sys = System::new();
-    match sys.cpu_load_aggregate() {
-        Ok(cpu) => {
-            println!("\nMeasuring CPU load...");
-            thread::sleep(Duration::from_secs(1));
-            let cpu = cpu.done().unwrap();
-            println!(
-                "CPU load: {}% user, {}% nice, {}% system, {}% intr, {}% idle ",
-                cpu.user * 100.0,
-                cpu.nice * 100.0,
-                cpu.system * 100.0,
-                cpu.interrupt * 100.0,
-                cpu.idle * 100.0
-            );
+impl CpuStats {
+    /// Fraction of time the cpu was not idle
+    pub fn busy(&self) -> f32 {
+        self.user + self.nice + self.system + self.interrupt
+    }
+}
+
+/// Fraction of all cpu time spent in every `/proc/stat` state
+///
+/// Guest time is also included in `user` and `nice`, as the kernel reports it.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct CpuBreakdown {
+    pub user:       f32,
+    pub nice:       f32,
+    pub system:     f32,
+    pub idle:       f32,
+    pub iowait:     f32,
+    pub irq:        f32,
+    pub softirq:    f32,
+    pub steal:      f32,
+    pub guest:      f32,
+    pub guest_nice: f32,
+}
+
+impl CpuBreakdown {
+    /// Fraction of time the cpu was busy, optionally counting iowait and steal
+    pub fn busy(&self, iowait: bool, steal: bool) -> f32 {
+        let mut busy = self.user + self.nice + self.system + self.irq + self.softirq;
+        if iowait {
+            busy += self.iowait;
+        }
+        if steal {
+            busy += self.steal;
+        }
+        busy
+    }
+
+    /// Collapse into the states of `CpuStats`, in the same basis as `busy`
+    ///
+    /// Whatever `busy` does not count (iowait and steal unless selected) is
+    /// reported as idle.
+    pub fn stats(&self, iowait: bool, steal: bool) -> CpuStats {
+        CpuStats {
+            user:      self.user,
+            nice:      self.nice,
+            system:    self.system,
+            interrupt: self.irq + self.softirq,
+            idle:      1.0 - self.busy(iowait, steal),
+        }
+    }
+
+    /// Label and value of each state, in `/proc/stat` order
+    pub fn rows(&self) -> Vec<(&'static str, f32)> {
+        vec![
+            ("user", self.user),
+            ("nice", self.nice),
+            ("system", self.system),
+            ("idle", self.idle),
+            ("iowait", self.iowait),
+            ("irq", self.irq),
+            ("softirq", self.softirq),
+            ("steal", self.steal),
+            ("guest", self.guest),
+            ("guest_nice", self.guest_nice),
+        ]
+    }
+}
+
+/// Cumulative cpu time counters (in clock ticks) from a `/proc/stat` line
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CpuTimes {
+    pub user:       u64,
+    pub nice:       u64,
+    pub system:     u64,
+    pub idle:       u64,
+    pub iowait:     u64,
+    pub irq:        u64,
+    pub softirq:    u64,
+    pub steal:      u64,
+    pub guest:      u64,
+    pub guest_nice: u64,
+}
+
+impl CpuTimes {
+    /// Parse the counters of a `/proc/stat` cpu line (e.g., `cpu  1 2 3 ...`)
+    pub fn parse(line: &str) -> Option<CpuTimes> {
+        let mut fields = line.split_whitespace();
+        if !fields.next()?.starts_with("cpu") {
+            return None;
+        }
+        let values = fields
+            .map(str::parse)
+            .collect::<std::result::Result<Vec<u64>, _>>()
+            .ok()?;
+        if values.len() < 4 {
+            return None;
+        }
+        let get = |i: usize| values.get(i).cloned().unwrap_or(0);
+        Some(CpuTimes {
+            user:       get(0),
+            nice:       get(1),
+            system:     get(2),
+            idle:       get(3),
+            iowait:     get(4),
+            irq:        get(5),
+            softirq:    get(6),
+            steal:      get(7),
+            guest:      get(8),
+            guest_nice: get(9),
+        })
+    }
+
+    /// Read the aggregate counters for all cpus
+    #[cfg(target_os = "linux")]
+    pub fn read() -> Result<CpuTimes> {
+        use std::io::{Error, ErrorKind};
+
+        let stat = std::fs::read_to_string("/proc/stat")?;
+        stat.lines().find_map(CpuTimes::parse).ok_or_else(|| {
+            Error::new(ErrorKind::InvalidData, "no cpu line found in /proc/stat").into()
+        })
+    }
+
+    /// Read the counters of each cpu (`cpu0`, `cpu1`, ...) in order
+    #[cfg(target_os = "linux")]
+    pub fn read_cores() -> Result<Vec<CpuTimes>> {
+        let stat = std::fs::read_to_string("/proc/stat")?;
+        Ok(stat
+            .lines()
+            .filter(|line| line.starts_with("cpu") && !line.starts_with("cpu "))
+            .filter_map(CpuTimes::parse)
+            .collect())
+    }
+
+    /// All counters but guest time, which `user` and `nice` already include
+    fn total(&self) -> u64 {
+        self.user
+            + self.nice
+            + self.system
+            + self.idle
+            + self.iowait
+            + self.irq
+            + self.softirq
+            + self.steal
+    }
+
+    /// Cpu usage between `prev` and `self`, or `None` if no time has passed
+    ///
+    /// Same as `breakdown_since` collapsed with `CpuBreakdown::stats(false, false)`,
+    /// so both share one total.
+    pub fn since(&self, prev: &CpuTimes) -> Option<CpuStats> {
+        Some(self.breakdown_since(prev)?.stats(false, false))
+    }
+
+    /// Share of every state between `prev` and `self`, or `None` if no time has passed
+    pub fn breakdown_since(&self, prev: &CpuTimes) -> Option<CpuBreakdown> {
+        let total = self.total().checked_sub(prev.total())?;
+        if total == 0 {
+            return None;
         }
-        Err(x) => println!("\nCPU load: error: {}", x),
+        let frac = |now: u64, then: u64| now.saturating_sub(then) as f32 / total as f32;
+        Some(CpuBreakdown {
+            user:       frac(self.user, prev.user),
+            nice:       frac(self.nice, prev.nice),
+            system:     frac(self.system, prev.system),
+            idle:       frac(self.idle, prev.idle),
+            iowait:     frac(self.iowait, prev.iowait),
+            irq:        frac(self.irq, prev.irq),
+            softirq:    frac(self.softirq, prev.softirq),
+            steal:      frac(self.steal, prev.steal),
+            guest:      frac(self.guest, prev.guest),
+            guest_nice: frac(self.guest_nice, prev.guest_nice),
+        })
+    }
+}
+
+impl std::fmt::Display for CpuTimes {
+    /// Write counters in `/proc/stat` order
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "cpu {} {} {} {} {} {} {} {} {} {}",
+            self.user,
+            self.nice,
+            self.system,
+            self.idle,
+            self.iowait,
+            self.irq,
+            self.softirq,
+            self.steal,
+            self.guest,
+            self.guest_nice
+        )
     }
 }
 
+/// Reported when no cpu time passed between two samples
 #[cfg(target_os = "linux")]
-fn get_cpu(interval: Option<u64>) {
-    use std::thread;
-    use systemstat::{Duration, Platform, System};
-
-    let sys = System::new();
-    match sys.cpu_load_aggregate() {
-        Ok(cpu) => {
-            thread::sleep(Duration::from_secs(interval.unwrap_or(1)));
-            let cpu = cpu.done().unwrap();
-            println!(
-                "{:.1}%",
-                (cpu.user + cpu.nice + cpu.system + cpu.interrupt) * 100_f32
-            );
+const IDLE: CpuStats = CpuStats {
+    user:      0.0,
+    nice:      0.0,
+    system:    0.0,
+    interrupt: 0.0,
+    idle:      1.0,
+};
+
+/// Reported by breakdowns when no cpu time passed between two samples
+#[cfg(target_os = "linux")]
+const IDLE_BREAKDOWN: CpuBreakdown = CpuBreakdown {
+    user:       0.0,
+    nice:       0.0,
+    system:     0.0,
+    idle:       1.0,
+    iowait:     0.0,
+    irq:        0.0,
+    softirq:    0.0,
+    steal:      0.0,
+    guest:      0.0,
+    guest_nice: 0.0,
+};
+
+/// Sample aggregate cpu load over `interval`
+#[cfg(target_os = "linux")]
+pub fn get_cpu(interval: Duration) -> Result<CpuStats> {
+    let start = CpuTimes::read()?;
+    std::thread::sleep(interval);
+    Ok(CpuTimes::read()?.since(&start).unwrap_or(IDLE))
+}
+
+/// Sample time spent in every aggregate cpu state over `interval`
+#[cfg(target_os = "linux")]
+pub fn get_cpu_breakdown(interval: Duration) -> Result<CpuBreakdown> {
+    let start = CpuTimes::read()?;
+    std::thread::sleep(interval);
+    Ok(CpuTimes::read()?
+        .breakdown_since(&start)
+        .unwrap_or(IDLE_BREAKDOWN))
+}
+
+/// Sample load of each cpu over `interval`
+#[cfg(target_os = "linux")]
+pub fn get_cpu_per_core(interval: Duration) -> Result<Vec<CpuStats>> {
+    let start = CpuTimes::read_cores()?;
+    std::thread::sleep(interval);
+    let end = CpuTimes::read_cores()?;
+    Ok(end
+        .iter()
+        .zip(&start)
+        .map(|(now, prev)| now.since(prev).unwrap_or(IDLE))
+        .collect())
+}
+
+/// Cpu load since the counters saved by the previous call, without sleeping
+/// (see `state::since_last`)
+#[cfg(target_os = "linux")]
+pub fn get_cpu_since_last(max_age: Duration, fallback: Duration) -> Result<CpuStats> {
+    let read = || CpuTimes::read().map(|times| vec![times]);
+    let pairs = since_last("cpu", read, max_age, fallback)?;
+    Ok(pairs
+        .first()
+        .and_then(|(now, prev)| now.since(prev))
+        .unwrap_or(IDLE))
+}
+
+/// Time spent in every cpu state since the previous call, like `get_cpu_since_last`
+#[cfg(target_os = "linux")]
+pub fn get_cpu_breakdown_since_last(
+    max_age: Duration,
+    fallback: Duration,
+) -> Result<CpuBreakdown> {
+    let read = || CpuTimes::read().map(|times| vec![times]);
+    let pairs = since_last("cpu", read, max_age, fallback)?;
+    Ok(pairs
+        .first()
+        .and_then(|(now, prev)| now.breakdown_since(prev))
+        .unwrap_or(IDLE_BREAKDOWN))
+}
+
+/// Load of each cpu since the previous call, like `get_cpu_since_last`
+#[cfg(target_os = "linux")]
+pub fn get_cpu_per_core_since_last(
+    max_age: Duration,
+    fallback: Duration,
+) -> Result<Vec<CpuStats>> {
+    let pairs = since_last("cpu-cores", CpuTimes::read_cores, max_age, fallback)?;
+    Ok(pairs
+        .iter()
+        .map(|(now, prev)| now.since(prev).unwrap_or(IDLE))
+        .collect())
+}
+
+/// Counters of one or more `/proc/stat` lines, saved one per line
+#[cfg(target_os = "linux")]
+impl Sample for Vec<CpuTimes> {
+    fn to_state(&self) -> String {
+        self.iter().map(CpuTimes::to_string).collect::<Vec<_>>().join("\n")
+    }
+
+    fn from_state(s: &str) -> Option<Self> {
+        s.lines().map(CpuTimes::parse).collect()
+    }
+
+    fn settled_since(&self, prev: &Self) -> bool {
+        // fewer ticks than this since the saved sample is too noisy to report
+        const MIN_TICKS: u64 = 25;
+
+        self.len() == prev.len()
+            && self
+                .iter()
+                .zip(prev)
+                .all(|(now, prev)| now.total().saturating_sub(prev.total()) >= MIN_TICKS)
+    }
+}
+
+/// Pair counters from `read` with those saved in state `name`
+///
+/// Returns `(now, prev)` for each line read.
+#[cfg(target_os = "linux")]
+fn since_last<F>(
+    name: &str,
+    read: F,
+    max_age: Duration,
+    fallback: Duration,
+) -> Result<Vec<(CpuTimes, CpuTimes)>>
+where
+    F: Fn() -> Result<Vec<CpuTimes>>,
+{
+    let (now, prev) = state::since_last(name, read, max_age, fallback)?;
+    Ok(now.into_iter().zip(prev).collect())
+}
+
+/// Sample cpu load of the current cgroup over `interval`, relative to its quota
+///
+/// Returns `None` if the cgroup has no cpu quota. The cgroup only reports
+/// total cpu time, so all busy time is reported as `user`.
+pub fn get_cgroup_cpu(interval: Duration) -> Option<CpuStats> {
+    use crate::cgroup;
+
+    let quota = cgroup::cpu_quota()?;
+    let start = CgroupUsage::read()?;
+    std::thread::sleep(interval);
+    CgroupUsage::read()?.since(&start, quota)
+}
+
+/// Cgroup cpu load since the usage saved by the previous call, like
+/// `get_cpu_since_last`
+pub fn get_cgroup_cpu_since_last(max_age: Duration, fallback: Duration) -> Option<CpuStats> {
+    use crate::cgroup;
+
+    let quota = cgroup::cpu_quota()?;
+    let read = || {
+        CgroupUsage::read()
+            .ok_or_else(|| Error::new(ErrorKind::NotFound, "no cgroup cpu usage found").into())
+    };
+    let (now, prev) = state::since_last("cgroup-cpu", read, max_age, fallback).ok()?;
+    now.since(&prev, quota)
+}
+
+/// Total cpu time used by the current cgroup at a point in time
+#[derive(Debug, Clone, Copy)]
+struct CgroupUsage {
+    /// Nanoseconds since the unix epoch
+    time_ns: u128,
+    used_ns: u128,
+}
+
+impl CgroupUsage {
+    fn read() -> Option<CgroupUsage> {
+        use std::time::{SystemTime, UNIX_EPOCH};
+
+        Some(CgroupUsage {
+            used_ns: crate::cgroup::cpu_usage()?.as_nanos(),
+            time_ns: SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_nanos(),
+        })
+    }
+
+    /// Busy time relative to `quota` cpus, or `None` if no time has passed
+    fn since(&self, prev: &CgroupUsage, quota: f64) -> Option<CpuStats> {
+        let elapsed = self.time_ns.checked_sub(prev.time_ns)?;
+        if elapsed == 0 {
+            return None;
         }
-        Err(x) => println!("\nCPU load: error: {}", x),
+        let used = self.used_ns.checked_sub(prev.used_ns)?;
+        let busy = (used as f64 / (elapsed as f64 * quota)).min(1.0) as f32;
+        Some(CpuStats {
+            user:      busy,
+            nice:      0.0,
+            system:    0.0,
+            interrupt: 0.0,
+            idle:      1.0 - busy,
+        })
+    }
+}
+
+impl Sample for CgroupUsage {
+    fn to_state(&self) -> String {
+        format!("{} {}", self.time_ns, self.used_ns)
+    }
+
+    fn from_state(s: &str) -> Option<Self> {
+        let mut fields = s.split_whitespace().map(str::parse::<u128>);
+        Some(CgroupUsage {
+            time_ns: fields.next()?.ok()?,
+            used_ns: fields.next()?.ok()?,
+        })
+    }
+
+    fn settled_since(&self, prev: &Self) -> bool {
+        // shorter than this since the saved sample is too noisy to report
+        const MIN_ELAPSED_NS: u128 = 250_000_000;
+
+        self.time_ns.saturating_sub(prev.time_ns) >= MIN_ELAPSED_NS
     }
 }
 
 #[cfg(target_os = "macos")]
-fn cpu_test() {
-    // use sys_info;
-    // sys_info::
+pub fn get_cpu(interval: Duration) -> Result<CpuStats> {
+    let _ = interval;
+    Err(unsupported())
+}
+
+#[cfg(target_os = "macos")]
+pub fn get_cpu_since_last(max_age: Duration, fallback: Duration) -> Result<CpuStats> {
+    let _ = (max_age, fallback);
+    Err(unsupported())
 }
 
 #[cfg(target_os = "macos")]
-fn get_cpu(interval: Option<u64>) {
+pub fn get_cpu_breakdown(interval: Duration) -> Result<CpuBreakdown> {
     let _ = interval;
-    // use sys_info;
-    // sys_info::
-}
-
-pub fn main(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
-    log::debug!("Args: {:?}", args);
-
-    let mut opts = Options::new();
-    opts.optflag("h", "help", "print this help menu");
-    opts.optflag("t", "test", "test cpu load aggregate and print results");
-    opts.optopt(
-        "i",
-        "interval",
-        "interval length for sampling cpu (in seconds)",
-        "SECS",
-    );
-
-    let matches = match opts.parse(&args[1..]) {
-        Ok(m) => m,
-        Err(f) => panic!("{}", f.to_string()),
-    };
+    Err(unsupported())
+}
+
+#[cfg(target_os = "macos")]
+pub fn get_cpu_breakdown_since_last(
+    max_age: Duration,
+    fallback: Duration,
+) -> Result<CpuBreakdown> {
+    let _ = (max_age, fallback);
+    Err(unsupported())
+}
+
+#[cfg(target_os = "macos")]
+pub fn get_cpu_per_core(interval: Duration) -> Result<Vec<CpuStats>> {
+    let _ = interval;
+    Err(unsupported())
+}
 
-    if matches.opt_present("h") {
-        print_help(&args[0], opts);
-        return Ok(());
+#[cfg(target_os = "macos")]
+pub fn get_cpu_per_core_since_last(
+    max_age: Duration,
+    fallback: Duration,
+) -> Result<Vec<CpuStats>> {
+    let _ = (max_age, fallback);
+    Err(unsupported())
+}
+
+#[cfg(target_os = "macos")]
+fn unsupported() -> Box<dyn std::error::Error> {
+    use std::io::{Error, ErrorKind};
+
+    Box::new(Error::new(
+        ErrorKind::Other,
+        "cpu load is not supported on macos",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn times(line: &str) -> CpuTimes {
+        CpuTimes::parse(line).unwrap()
     }
 
-    if matches.opt_present("t") {
-        cpu_test();
+    #[test]
+    fn parse() {
+        let cpu = times("cpu  1 2 3 4 5 6 7 8 9 10");
+        assert_eq!(cpu.user, 1);
+        assert_eq!(cpu.steal, 8);
+        assert_eq!(cpu.guest_nice, 10);
+        // older kernels report fewer columns
+        assert_eq!(times("cpu0 1 2 3 4").iowait, 0);
+        assert!(CpuTimes::parse("cpu 1 2 3").is_none());
+        assert!(CpuTimes::parse("intr 1 2 3 4").is_none());
     }
 
-    let interval: Option<u64> = matches.opt_get("i")?;
-    get_cpu(interval);
-    Ok(())
+    #[test]
+    fn since() {
+        let prev = times("cpu 0 0 0 0 0 0 0 0 0 0");
+        // (now, user, system, interrupt, idle, busy)
+        let cases = [
+            ("cpu 50 0 0 50 0 0 0 0 0 0", 0.5, 0.0, 0.0, 0.5, 0.5),
+            ("cpu 50 0 0 0 0 0 0 50 0 0", 0.5, 0.0, 0.0, 0.5, 0.5),
+            ("cpu 25 0 25 25 25 0 0 0 0 0", 0.25, 0.25, 0.0, 0.5, 0.5),
+            ("cpu 0 0 0 50 0 25 25 0 0 0", 0.0, 0.0, 0.5, 0.5, 0.5),
+            ("cpu 50 0 0 50 0 0 0 0 50 0", 0.5, 0.0, 0.0, 0.5, 0.5),
+        ];
+        for (now, user, system, interrupt, idle, busy) in cases.iter() {
+            let cpu = times(now).since(&prev).unwrap();
+            assert_eq!(
+                (cpu.user, cpu.system, cpu.interrupt, cpu.idle, cpu.busy()),
+                (*user, *system, *interrupt, *idle, *busy),
+                "{}",
+                now
+            );
+        }
+        assert!(prev.since(&prev).is_none());
+        assert!(prev.since(&times("cpu 1 0 0 0")).is_none());
+    }
+
+    #[test]
+    fn breakdown_since() {
+        let prev = times("cpu 10 0 10 10 0 0 0 0 0 0");
+        let now = times("cpu 50 0 10 30 20 0 0 20 0 0");
+        let cpu = now.breakdown_since(&prev).unwrap();
+        assert_eq!((cpu.user, cpu.idle, cpu.iowait, cpu.steal), (0.4, 0.2, 0.2, 0.2));
+        // (iowait, steal, busy)
+        let cases = [
+            (false, false, 0.4),
+            (true, false, 0.6),
+            (false, true, 0.6),
+            (true, true, 0.8),
+        ];
+        for (iowait, steal, busy) in cases.iter() {
+            let stats = cpu.stats(*iowait, *steal);
+            assert_eq!(cpu.busy(*iowait, *steal), *busy, "{} {}", iowait, steal);
+            assert_eq!(stats.idle, 1.0 - busy, "{} {}", iowait, steal);
+        }
+        // the default headline shares the breakdown's total
+        assert_eq!(now.since(&prev).unwrap().busy(), cpu.busy(false, false));
+        assert!(now.breakdown_since(&now).is_none());
+    }
 }
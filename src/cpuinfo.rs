@@ -0,0 +1,141 @@
+/* Collect cpu model and clock speed info */
+use crate::Result;
+use serde::{Deserialize, Serialize};
+#[cfg(target_os = "linux")]
+use std::{collections::BTreeSet, fs, path::Path};
+
+/// Where the kernel exposes per-cpu topology and cpufreq info
+pub const CPU_ROOT: &str = "/sys/devices/system/cpu";
+
+/// Clock speed of one logical cpu (in kHz)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoreFreq {
+    /// Number of the logical cpu, e.g. `3` for `cpu3`
+    pub cpu:      usize,
+    pub current:  Option<u64>,
+    /// Lowest and highest speed the cpu supports
+    pub min:      Option<u64>,
+    pub max:      Option<u64>,
+    /// Active cpufreq scaling governor, e.g. `powersave`
+    pub governor: Option<String>,
+}
+
+/// Model, topology and clock speeds of the installed cpus
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CpuInfo {
+    pub model:   Option<String>,
+    /// Physical cores
+    pub cores:   usize,
+    /// Logical cpus (hardware threads)
+    pub threads: usize,
+    pub freqs:   Vec<CoreFreq>,
+}
+
+impl CpuInfo {
+    /// Average current speed of all cpus that report one (in kHz)
+    pub fn avg_freq(&self) -> Option<u64> {
+        let current = self
+            .freqs
+            .iter()
+            .filter_map(|f| f.current)
+            .collect::<Vec<_>>();
+        if current.is_empty() {
+            return None;
+        }
+        Some(current.iter().sum::<u64>() / current.len() as u64)
+    }
+
+    /// Lowest supported speed of any cpu (in kHz)
+    pub fn min_freq(&self) -> Option<u64> {
+        self.freqs.iter().filter_map(|f| f.min).min()
+    }
+
+    /// Highest supported speed of any cpu (in kHz)
+    pub fn max_freq(&self) -> Option<u64> {
+        self.freqs.iter().filter_map(|f| f.max).max()
+    }
+
+    /// Governor of the first cpu, which is normally shared by all
+    pub fn governor(&self) -> Option<&str> {
+        self.freqs.iter().find_map(|f| f.governor.as_deref())
+    }
+}
+
+/// Model from `/proc/cpuinfo` and speeds from cpufreq under `CPU_ROOT`
+///
+/// Cpus without cpufreq (e.g. most VMs) fall back to the `cpu MHz` value of
+/// `/proc/cpuinfo` for their current speed.
+#[cfg(target_os = "linux")]
+pub fn get_cpu_info() -> Result<CpuInfo> {
+    let cpuinfo = fs::read_to_string("/proc/cpuinfo")?;
+    let mut model = None;
+    // `processor` number and `cpu MHz` of each online cpu; offline cpus are
+    // left out, so numbers can have gaps
+    let mut processors: Vec<(usize, Option<f64>)> = Vec::new();
+    let mut cores = BTreeSet::new();
+    let mut physical_id = None;
+    for line in cpuinfo.lines() {
+        let mut fields = line.splitn(2, ':').map(str::trim);
+        let (key, value) = match (fields.next(), fields.next()) {
+            (Some(key), Some(value)) => (key, value),
+            _ => continue,
+        };
+        match key {
+            "processor" => {
+                if let Ok(cpu) = value.parse() {
+                    processors.push((cpu, None));
+                }
+            }
+            "model name" if model.is_none() => model = Some(value.to_string()),
+            "cpu MHz" => {
+                if let Some((_, mhz)) = processors.last_mut() {
+                    *mhz = value.parse::<f64>().ok();
+                }
+            }
+            "physical id" => physical_id = Some(value.to_string()),
+            "core id" => {
+                cores.insert((physical_id.clone(), value.to_string()));
+            }
+            _ => (),
+        }
+    }
+
+    let threads = processors.len();
+    let freqs = processors
+        .into_iter()
+        .map(|(cpu, mhz)| {
+            let dir = Path::new(CPU_ROOT).join(format!("cpu{}/cpufreq", cpu));
+            let read = |file: &str| {
+                fs::read_to_string(dir.join(file))
+                    .ok()
+                    .map(|s| s.trim().to_string())
+            };
+            let khz = |file: &str| read(file).and_then(|s| s.parse::<u64>().ok());
+            let fallback = mhz.map(|mhz| (mhz * 1000.0).round() as u64);
+            CoreFreq {
+                cpu,
+                current: khz("scaling_cur_freq").or(fallback),
+                min: khz("cpuinfo_min_freq"),
+                max: khz("cpuinfo_max_freq"),
+                governor: read("scaling_governor"),
+            }
+        })
+        .collect();
+    Ok(CpuInfo {
+        model,
+        // architectures without core ids report one core per thread
+        cores: if cores.is_empty() { threads } else { cores.len() },
+        threads,
+        freqs,
+    })
+}
+
+#[cfg(target_os = "macos")]
+pub fn get_cpu_info() -> Result<CpuInfo> {
+    use std::io::{Error, ErrorKind};
+
+    Err(Box::new(Error::new(
+        ErrorKind::Other,
+        "cpu info is not supported on macos",
+    )))
+}
@@ -0,0 +1,139 @@
+/* Collect metrics in the background and serve them over a unix socket */
+//! The daemon writes its latest `Snapshot` as json to every client that
+//! connects to `socket_path()`, then closes the connection.
+use crate::{
+    cgroup,
+    cpu::{get_cgroup_cpu, get_cpu, CpuStats},
+    load::{get_load, LoadAvg},
+    memory::{get_memory, MemStats},
+    state,
+    temp::{get_temp, Temp},
+    uptime::{get_uptime, Uptime},
+    Result,
+};
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    io::{Error, ErrorKind, Write},
+    os::unix::net::{UnixListener, UnixStream},
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Latest value of every metric; `None` if it could not be collected
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// Sampling cadence of the daemon
+    pub interval_secs: u64,
+    pub cpu:           Option<CpuStats>,
+    /// Cpus the daemon's cgroup may use; `cpu` is relative to it if set
+    #[serde(default)]
+    pub cpu_quota:     Option<f64>,
+    pub memory:        Option<MemStats>,
+    pub load:          Option<LoadAvg>,
+    pub temp:          Option<Temp>,
+    pub uptime:        Option<Uptime>,
+}
+
+impl Snapshot {
+    /// Collect all metrics; cpu load is sampled over `interval`
+    ///
+    /// Cpu load is relative to the cgroup's quota if it has one, unless `host`.
+    fn collect(interval: Duration, host: bool) -> Snapshot {
+        fn ok<T>(name: &str, result: Result<T>) -> Option<T> {
+            result
+                .map_err(|e| debug!("Error collecting {}: {}", name, e))
+                .ok()
+        }
+        let cpu_quota = if host { None } else { cgroup::cpu_quota() };
+        let cpu = match cpu_quota {
+            Some(_) => get_cgroup_cpu(interval),
+            None => ok("cpu", get_cpu(interval)),
+        };
+        Snapshot {
+            interval_secs: interval.as_secs(),
+            cpu,
+            cpu_quota,
+            memory:        ok("memory", get_memory()),
+            load:          ok("load", get_load()),
+            temp:          ok("temp", get_temp()),
+            uptime:        ok("uptime", get_uptime()),
+        }
+    }
+}
+
+/// Location of the daemon's socket, in the private state dir
+///
+/// Fails if the state dir does not exist yet; only `run` creates it.
+pub fn socket_path() -> Result<PathBuf> {
+    Ok(state::owned_dir()?.join("daemon.sock"))
+}
+
+/// Get the latest snapshot from a running daemon
+pub fn query() -> Result<Snapshot> {
+    let stream = UnixStream::connect(socket_path()?)?;
+    stream.set_read_timeout(Some(Duration::from_millis(500)))?;
+    Ok(serde_json::from_reader(stream)?)
+}
+
+/// Sample metrics every `interval` and serve them until the process exits
+///
+/// With `host`, cpu load is host-wide even inside a cgroup with a quota.
+pub fn run(interval: Duration, host: bool) -> Result {
+    // the sampler would otherwise spin without ever sleeping
+    if interval < Duration::from_secs(1) {
+        return Err(Box::new(Error::new(
+            ErrorKind::InvalidInput,
+            format!("invalid interval `{}` (expected at least 1 second)", interval.as_secs()),
+        )));
+    }
+    state::private_dir()?;
+    let path = socket_path()?;
+    if UnixStream::connect(&path).is_ok() {
+        return Err(Box::new(Error::new(
+            ErrorKind::AddrInUse,
+            format!("daemon already listening on {:?}", path),
+        )));
+    }
+    // left behind by a daemon that did not exit cleanly
+    if path.exists() {
+        fs::remove_file(&path)?;
+    }
+    let listener = UnixListener::bind(&path)?;
+    info!("Listening on {:?}", path);
+
+    let latest = Arc::new(Mutex::new(Snapshot::collect(interval, host)));
+    let sampler = Arc::clone(&latest);
+    thread::spawn(move || loop {
+        let started = Instant::now();
+        let snapshot = Snapshot::collect(interval, host);
+        match sampler.lock() {
+            Ok(mut latest) => *latest = snapshot,
+            Err(e) => warn!("Error storing snapshot: {}", e),
+        }
+        // cpu sampling usually takes up the whole interval already
+        if let Some(rest) = interval.checked_sub(started.elapsed()) {
+            thread::sleep(rest);
+        }
+    });
+
+    for stream in listener.incoming() {
+        if let Err(e) = stream.map_err(Into::into).and_then(|s| serve(s, &latest)) {
+            warn!("Error serving client: {}", e);
+        }
+    }
+    Ok(())
+}
+
+/// Write the latest snapshot to a client
+fn serve(mut stream: UnixStream, latest: &Mutex<Snapshot>) -> Result {
+    let json = {
+        let latest = latest.lock().map_err(|e| e.to_string())?;
+        serde_json::to_string(&*latest)?
+    };
+    stream.write_all(json.as_bytes())?;
+    Ok(())
+}
@@ -0,0 +1,105 @@
+/* Collect filesystem usage info */
+use crate::Result;
+use serde::{Deserialize, Serialize};
+use std::{
+    io::{Error, ErrorKind},
+    path::Path,
+};
+use systemstat::{Filesystem, Platform, System};
+
+/// Filesystem types that do not represent real storage
+pub const PSEUDO_FS: &[&str] = &[
+    "autofs",
+    "binfmt_misc",
+    "bpf",
+    "cgroup",
+    "cgroup2",
+    "configfs",
+    "debugfs",
+    "devpts",
+    "devtmpfs",
+    "efivarfs",
+    "fusectl",
+    "hugetlbfs",
+    "mqueue",
+    "nsfs",
+    "overlay",
+    "proc",
+    "pstore",
+    "ramfs",
+    "securityfs",
+    "squashfs",
+    "sysfs",
+    "tmpfs",
+    "tracefs",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiskStats {
+    /// Where the filesystem is mounted
+    pub mount:   String,
+    /// Device or source the filesystem is mounted from
+    pub device:  String,
+    pub fs_type: String,
+    pub total:   u64,
+    pub used:    u64,
+    /// Free bytes available to non-superuser
+    pub avail:   u64,
+}
+
+impl DiskStats {
+    fn new(fs: &Filesystem) -> DiskStats {
+        DiskStats {
+            mount:   fs.fs_mounted_on.clone(),
+            device:  fs.fs_mounted_from.clone(),
+            fs_type: fs.fs_type.clone(),
+            total:   fs.total.as_u64(),
+            used:    fs.total.as_u64().saturating_sub(fs.free.as_u64()),
+            avail:   fs.avail.as_u64(),
+        }
+    }
+
+    /// Used space as a percentage of space usable by non-superusers (like `df`)
+    pub fn used_pct(&self) -> f32 {
+        match self.used + self.avail {
+            0 => 0.0,
+            usable => (self.used as f32 / usable as f32) * 100.0,
+        }
+    }
+
+    pub fn is_pseudo(&self) -> bool {
+        PSEUDO_FS.contains(&self.fs_type.as_str()) || self.total == 0
+    }
+}
+
+/// Usage of all mounted filesystems that are not pseudo filesystems
+pub fn get_disks() -> Result<Vec<DiskStats>> {
+    let sys = System::new();
+    Ok(sys
+        .mounts()?
+        .iter()
+        .map(DiskStats::new)
+        .filter(|disk| !disk.is_pseudo())
+        .collect())
+}
+
+/// Usage of the filesystem containing `path`
+pub fn get_disk<P: AsRef<Path>>(path: P) -> Result<DiskStats> {
+    let path = path
+        .as_ref()
+        .canonicalize()
+        .map_err(|e| format!("error reading {:?}: {}", path.as_ref(), e))?;
+    let sys = System::new();
+    sys.mounts()?
+        .iter()
+        .filter(|fs| path.starts_with(&fs.fs_mounted_on))
+        .max_by_key(|fs| fs.fs_mounted_on.len())
+        .map(DiskStats::new)
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::NotFound,
+                format!("no filesystem found for {:?}", path),
+            )
+            .into()
+        })
+}
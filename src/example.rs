@@ -27,7 +27,7 @@ pub fn run_all(measure_cpu: bool) {
                 println!("{}: {:#?}", blkstats.name, blkstats);
             }
         }
-        Err(x) => println!("\nBlock statistics error: {}", x.to_string()),
+        Err(x) => println!("\nBlock statistics error: {}", x),
     }
 
     match sys.networks() {
@@ -59,7 +59,7 @@ pub fn run_all(measure_cpu: bool) {
             "\nBattery: {}%, {}h{}m remaining",
             battery.remaining_capacity * 100.0,
             battery.remaining_time.as_secs() / 3600,
-            battery.remaining_time.as_secs() % 60
+            battery.remaining_time.as_secs() / 60 % 60
         ),
         Err(x) => print!("\nBattery: error: {}", x),
     }
@@ -124,6 +124,6 @@ pub fn run_all(measure_cpu: bool) {
 
     match sys.socket_stats() {
         Ok(stats) => println!("\nSystem socket statistics: {:#?}", stats),
-        Err(x) => println!("\nError: {}", x.to_string()),
+        Err(x) => println!("\nError: {}", x),
     }
 }
@@ -0,0 +1,111 @@
+/* Collect fan speed info */
+use crate::{
+    hwmon::{self, HWMON},
+    Result,
+};
+use log::debug;
+use serde::{Deserialize, Serialize};
+use std::{
+    io::{Error, ErrorKind},
+    path::Path,
+};
+
+/// A fan of a hwmon chip
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Fan {
+    /// Unique name of the hwmon directory and input, e.g. `hwmon4/fan1`
+    pub name:  String,
+    /// Hwmon label, or the chip name if the fan has none
+    pub label: String,
+    /// Revolutions per minute
+    pub rpm:   u64,
+}
+
+/// All fans of the hwmon chips under `root` (normally `HWMON`)
+pub fn get_fans_from<P: AsRef<Path>>(root: P) -> Vec<Fan> {
+    let fans = hwmon::inputs(root.as_ref(), "fan")
+        .into_iter()
+        .map(|input| Fan {
+            name:  format!("{}/{}", input.dir, input.sensor),
+            rpm:   input.value as u64,
+            label: input.label.unwrap_or(input.chip),
+        })
+        .collect();
+    debug!("Fans: {:#?}", fans);
+    fans
+}
+
+/// All fans of hwmon chips
+pub fn get_fans() -> Vec<Fan> {
+    get_fans_from(HWMON)
+}
+
+/// Fan whose name or label is `name`, or the fastest fan if `None`
+///
+/// Returns `Ok(None)` if the machine has no fans at all.
+pub fn get_fan(name: Option<&str>) -> Result<Option<Fan>> {
+    get_fan_from(HWMON, name)
+}
+
+/// Fan named `name` under `root`, or the fastest fan if `None`
+pub fn get_fan_from<P: AsRef<Path>>(root: P, name: Option<&str>) -> Result<Option<Fan>> {
+    let fans = get_fans_from(root);
+    if fans.is_empty() {
+        return Ok(None);
+    }
+    let fan = match name {
+        Some(name) => fans
+            .into_iter()
+            .find(|fan| fan.name == name || fan.label == name),
+        None => fans.into_iter().max_by_key(|fan| fan.rpm),
+    };
+    fan.map(Some).ok_or_else(|| {
+        let name = name.unwrap_or_default();
+        Error::new(ErrorKind::NotFound, format!("no fan named `{}` found", name)).into()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::{fixture_dir, write_fixture};
+    use std::fs;
+
+    #[test]
+    fn fans() {
+        // two chips of the same driver, as on some dell laptops
+        let root = fixture_dir("fan-fans");
+        write_fixture(&root.join("hwmon0"), &[("name", "dell_smm"), ("fan1_input", "2400")]);
+        write_fixture(&root.join("hwmon1"), &[
+            ("name", "dell_smm"),
+            ("fan1_input", "3100"),
+            ("fan1_label", "GPU fan"),
+        ]);
+        write_fixture(&root.join("hwmon2"), &[("name", "coretemp"), ("temp1_input", "50000")]);
+
+        let fans = get_fans_from(&root);
+        let summary: Vec<_> = fans
+            .iter()
+            .map(|f| (f.name.as_str(), f.label.as_str(), f.rpm))
+            .collect();
+        assert_eq!(summary, [
+            ("hwmon0/fan1", "dell_smm", 2400),
+            ("hwmon1/fan1", "GPU fan", 3100),
+        ]);
+
+        let name = |name| get_fan_from(&root, name).unwrap().map(|fan| fan.name);
+        assert_eq!(name(None).as_deref(), Some("hwmon1/fan1"));
+        assert_eq!(name(Some("hwmon0/fan1")).as_deref(), Some("hwmon0/fan1"));
+        assert_eq!(name(Some("GPU fan")).as_deref(), Some("hwmon1/fan1"));
+        assert!(get_fan_from(&root, Some("dell_smm/fan1")).is_err());
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn no_fans() {
+        let root = fixture_dir("fan-none");
+        assert!(get_fans_from(&root).is_empty());
+        assert!(get_fan_from(&root, Some("fan1")).unwrap().is_none());
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,86 @@
+/* Read inputs of hardware monitoring chips */
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Where the kernel exposes hardware monitoring chips
+pub const HWMON: &str = "/sys/class/hwmon";
+
+/// Raw value of one input of a chip, e.g. `coretemp`'s `temp1_input`
+#[derive(Debug, Clone)]
+pub(crate) struct Input {
+    /// Directory of the chip, e.g. `hwmon3`, which unlike its name is unique
+    pub dir:    String,
+    /// Name of the chip, e.g. `coretemp`
+    pub chip:   String,
+    /// Input without the `_input` suffix, e.g. `temp1`
+    pub sensor: String,
+    /// Contents of the matching `_label` file, if any
+    pub label:  Option<String>,
+    pub value:  f64,
+}
+
+/// Sorted subdirectories of `root` whose name starts with `prefix`
+pub(crate) fn entries(root: &Path, prefix: &str) -> Vec<PathBuf> {
+    let mut dirs = fs::read_dir(root)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|path| {
+                    path.file_name()
+                        .is_some_and(|name| name.to_string_lossy().starts_with(prefix))
+                })
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    // natural order, so that `thermal_zone10` follows `thermal_zone9`
+    dirs.sort_by_key(|dir| {
+        let name = dir.file_name().unwrap_or_default().to_string_lossy();
+        let digits = name.trim_start_matches(|c: char| !c.is_ascii_digit());
+        (digits.parse::<u64>().unwrap_or(0), name.into_owned())
+    });
+    dirs
+}
+
+/// Trimmed contents of `path`
+pub(crate) fn read(path: PathBuf) -> Option<String> {
+    fs::read_to_string(path).ok().map(|s| s.trim().to_string())
+}
+
+/// All `<kind>N_input` files of the chips under `root`, e.g. for `kind` `fan`
+pub(crate) fn inputs(root: &Path, kind: &str) -> Vec<Input> {
+    let mut found = Vec::new();
+    for chip in entries(root, "hwmon") {
+        let dir = chip.file_name().unwrap_or_default().to_string_lossy().into_owned();
+        let chip_name = read(chip.join("name")).unwrap_or_default();
+        let mut sensors = fs::read_dir(&chip)
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok())
+                    .map(|e| e.file_name().to_string_lossy().into_owned())
+                    .filter_map(|f| {
+                        let n = f.strip_prefix(kind)?.strip_suffix("_input")?;
+                        n.parse::<u32>().ok()
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        sensors.sort_unstable();
+        for n in sensors {
+            let sensor = format!("{}{}", kind, n);
+            let value = read(chip.join(format!("{}_input", sensor)))
+                .and_then(|s| s.parse::<f64>().ok());
+            if let Some(value) = value {
+                found.push(Input {
+                    dir: dir.clone(),
+                    chip: chip_name.clone(),
+                    label: read(chip.join(format!("{}_label", sensor))),
+                    sensor,
+                    value,
+                });
+            }
+        }
+    }
+    found
+}
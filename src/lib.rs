@@ -0,0 +1,41 @@
+//! System stats for use in shell, TMUX, etc.
+//!
+//! Each module exposes a collector function returning a typed struct, so
+//! the same calculations used by the `sysinfo` binary can be embedded in
+//! other tools.
+pub mod battery;
+pub mod cgroup;
+pub mod color;
+pub mod config;
+pub mod cpu;
+pub mod cpuinfo;
+pub mod daemon;
+pub mod disk;
+pub mod fan;
+pub mod hwmon;
+pub mod load;
+pub mod memory;
+pub mod net;
+pub mod state;
+pub mod temp;
+pub mod template;
+pub mod uptime;
+pub mod util;
+
+pub use battery::{get_battery, Battery};
+pub use cpu::{
+    get_cpu, get_cpu_breakdown, get_cpu_per_core, get_cpu_since_last, CpuBreakdown, CpuStats,
+};
+pub use cpuinfo::{get_cpu_info, CpuInfo};
+pub use disk::{get_disk, get_disks, DiskStats};
+pub use fan::{get_fan, get_fans, Fan};
+pub use load::{get_load, get_tasks, online_cpus, LoadAvg, Tasks, Trend};
+pub use memory::{
+    get_memory, get_memory_breakdown, get_memory_with, get_swap, Accounting, MemBreakdown,
+    MemStats,
+};
+pub use net::{get_net, get_net_since_last, NetStats};
+pub use temp::{get_sensors, get_temp, get_temp_with, Aggregate, Sensor, Temp, Unit};
+pub use uptime::{get_boot_time, get_uptime, Parts, Uptime};
+
+pub type Result<T = ()> = std::result::Result<T, Box<dyn std::error::Error>>;
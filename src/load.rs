@@ -1,51 +1,144 @@
-/* Output system load info */
-use getopts::Options;
-use log::debug;
-use std::io::{Error, ErrorKind};
+/* Collect system load info */
+use crate::Result;
+use serde::{Deserialize, Serialize};
+use std::{fs, thread};
 use systemstat::{Platform, System};
 
-fn print_help(command: &str, opts: Options) {
-    let usage = format!("Usage: {} {} [options]", super::PROG, command);
-    print!("{}", opts.usage(&usage));
+/// Load averages over the last 1, 5 and 15 minutes
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct LoadAvg {
+    pub one:     f32,
+    pub five:    f32,
+    pub fifteen: f32,
 }
 
-pub fn main(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
-    debug!("Args: {:?}", args);
+/// Direction of the 1-minute load relative to the 15-minute load
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Trend {
+    Rising,
+    Falling,
+    Stable,
+}
 
-    let mut opts = Options::new();
-    opts.optflag("h", "help", "print this help menu");
-    opts.optopt(
-        "n",
-        "number",
-        "number of load averages to show (1-3)",
-        "NUMBER",
-    );
+impl Trend {
+    pub fn arrow(self) -> &'static str {
+        match self {
+            Trend::Rising => "↑",
+            Trend::Falling => "↓",
+            Trend::Stable => "→",
+        }
+    }
+}
 
-    let matches = opts.parse(&args[1..])?;
+/// Scheduling entities from `/proc/loadavg`
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Tasks {
+    /// Currently runnable tasks
+    pub running:  u64,
+    /// All tasks (processes and threads)
+    pub total:    u64,
+    /// Most recently created pid
+    pub last_pid: u64,
+}
 
-    if matches.opt_present("h") {
-        print_help(&args[0], opts);
-        return Ok(());
+impl LoadAvg {
+    /// Average over the last `minutes` (1, 5 or 15)
+    pub fn window(&self, minutes: u32) -> Option<f32> {
+        match minutes {
+            1 => Some(self.one),
+            5 => Some(self.five),
+            15 => Some(self.fifteen),
+            _ => None,
+        }
     }
 
-    let sys = System::new();
-    let loadavg = sys.load_average()?;
+    /// Compare the 1-minute to the 15-minute average
+    ///
+    /// Differences within 10% (or 0.05 on a nearly idle system) are stable.
+    pub fn trend(&self) -> Trend {
+        let tolerance = (self.fifteen * 0.1).max(0.05);
+        if self.one - self.fifteen > tolerance {
+            Trend::Rising
+        } else if self.fifteen - self.one > tolerance {
+            Trend::Falling
+        } else {
+            Trend::Stable
+        }
+    }
 
-    let num = matches.opt_str("n").unwrap_or_else(|| String::from("3"));
-
-    match num.as_str() {
-        "1" => println!("{:.2}", loadavg.one),
-        "2" => println!("{:.2} {:.2}", loadavg.one, loadavg.five),
-        "3" => println!(
-            "{:.2} {:.2} {:.2}",
-            loadavg.one, loadavg.five, loadavg.fifteen
-        ),
-        _ => {
-            return Err(Box::new(Error::new(
-                ErrorKind::InvalidInput,
-                format!("no command matches `{}`", num),
-            )));
+    /// Load averages divided by `cpus`, where 1.0 means every cpu is busy
+    pub fn normalized(&self, cpus: usize) -> LoadAvg {
+        let cpus = cpus.max(1) as f32;
+        LoadAvg {
+            one:     self.one / cpus,
+            five:    self.five / cpus,
+            fifteen: self.fifteen / cpus,
         }
     }
-    Ok(())
+}
+
+/// Number of online cpus, from ranges like `0-3,6` in sysfs
+///
+/// Falls back to the number of cpus available to this process.
+pub fn online_cpus() -> usize {
+    let parallelism = || thread::available_parallelism().map_or(1, |n| n.get());
+    let online = match fs::read_to_string("/sys/devices/system/cpu/online") {
+        Ok(online) => online,
+        Err(_) => return parallelism(),
+    };
+    let count = online
+        .trim()
+        .split(',')
+        .map(|range| {
+            let mut bounds = range.splitn(2, '-').map(str::parse::<usize>);
+            match (bounds.next(), bounds.next()) {
+                (Some(Ok(start)), Some(Ok(end))) if end >= start => Some(end - start + 1),
+                (Some(Ok(_)), None) => Some(1),
+                _ => None,
+            }
+        })
+        .sum::<Option<usize>>();
+    count.filter(|&n| n > 0).unwrap_or_else(parallelism)
+}
+
+pub fn get_load() -> Result<LoadAvg> {
+    let sys = System::new();
+    let loadavg = sys.load_average()?;
+    Ok(LoadAvg {
+        one:     loadavg.one,
+        five:    loadavg.five,
+        fifteen: loadavg.fifteen,
+    })
+}
+
+/// Runnable and total tasks, e.g. `2/913` in `/proc/loadavg`
+#[cfg(target_os = "linux")]
+pub fn get_tasks() -> Result<Tasks> {
+    use std::io::{Error, ErrorKind};
+
+    let loadavg = fs::read_to_string("/proc/loadavg")?;
+    let invalid = || Error::new(ErrorKind::InvalidData, "invalid /proc/loadavg");
+    let mut fields = loadavg.split_whitespace().skip(3);
+    let mut tasks = fields.next().ok_or_else(invalid)?.splitn(2, '/');
+    let num = |field: Option<&str>| {
+        field
+            .and_then(|f| f.parse::<u64>().ok())
+            .ok_or_else(invalid)
+    };
+    Ok(Tasks {
+        running:  num(tasks.next())?,
+        total:    num(tasks.next())?,
+        last_pid: num(fields.next())?,
+    })
+}
+
+#[cfg(target_os = "macos")]
+pub fn get_tasks() -> Result<Tasks> {
+    use std::io::{Error, ErrorKind};
+
+    Err(Box::new(Error::new(
+        ErrorKind::Other,
+        "task counts are not supported on macos",
+    )))
 }
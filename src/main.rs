@@ -1,15 +1,11 @@
 use getopts::Options;
 use log::debug;
 use std::io::{Error, ErrorKind};
-mod cpu;
+use std::path::PathBuf;
+use sysinfo::{color::Style, config::Config, Result};
+mod cmd;
 mod example;
-mod load;
 mod logger;
-mod memory;
-mod temp;
-mod uptime;
-
-type Result<T = ()> = std::result::Result<T, Box<dyn std::error::Error>>;
 
 // Constants
 pub const PROG: &str = env!("CARGO_PKG_NAME");
@@ -49,7 +45,7 @@ fn main() -> Result {
     let args: Vec<String> = if std::env::args().len() > 1 {
         std::env::args().collect()
     } else {
-        vec!["sysinfo"].iter().map(|s| (*s).to_string()).collect()
+        ["sysinfo"].iter().map(|s| (*s).to_string()).collect()
     };
     let program = args[0].clone();
 
@@ -60,13 +56,45 @@ fn main() -> Result {
     opts.optflag("V", "version", "print version and exit");
     opts.optflagmulti("v", "verbose", "increase log verbosity (e.g., -vv/-vvv)");
     opts.optflag("q", "quiet", "discard log output (overrides --verbose");
+    cmd::toggle_opt(&mut opts, "j", "json", "output result as a json object");
+    opts.optopt(
+        "",
+        "color",
+        "color values above thresholds: none, tmux, ansi (default: none)",
+        "STYLE",
+    );
+    opts.optopt(
+        "",
+        "config",
+        "read defaults from FILE (default: ~/.config/sysinfo/config.toml)",
+        "FILE",
+    );
+    cmd::toggle_opt(
+        &mut opts,
+        "",
+        "host",
+        "report host-wide memory/cpu even inside a limited cgroup (container)",
+    );
+    cmd::toggle_opt(
+        &mut opts,
+        "",
+        "daemon",
+        "ask a running daemon for metrics first; on by default",
+    );
 
     let commands = vec![
         Command::new("m, memory", "output memory usage info"),
+        Command::new("d, disk", "output filesystem usage info"),
         Command::new("c, cpu", "output cpu usage info"),
+        Command::new("cpuinfo", "output cpu model and clock speed info"),
         Command::new("l, load", "output load average"),
+        Command::new("n, net", "output network throughput"),
         Command::new("t, temp", "output cpu temp"),
+        Command::new("fan", "output fan speed"),
         Command::new("u, uptime", "output system uptime"),
+        Command::new("b, battery", "output battery charge and time remaining"),
+        Command::new("daemon", "collect metrics in the background for other commands"),
+        Command::new("config", "print effective configuration"),
         Command::new("e, example", "show example output of different commands"),
     ];
 
@@ -100,7 +128,7 @@ fn main() -> Result {
     let cmd = if !matches.free.is_empty() {
         matches
             .free
-            .get(0)
+            .first()
             .ok_or_else(|| Error::new(ErrorKind::InvalidData, "error getting command"))?
     } else {
         print_help(&program, opts, commands);
@@ -109,13 +137,48 @@ fn main() -> Result {
 
     debug!("Command: '{}'", cmd);
 
+    // Load config; a missing file is only an error if given explicitly
+    let config_path = match matches.opt_str("config") {
+        Some(path) => Some(PathBuf::from(path)),
+        None => Config::default_path().filter(|path| path.exists()),
+    };
+    let mut config = match &config_path {
+        Some(path) => Config::load(path)?,
+        None => Config::default(),
+    };
+    // Global flags override config
+    config.json = cmd::toggle(&matches, "json", config.json);
+    if let Some(style) = matches.opt_get::<Style>("color")? {
+        config.color = style;
+    }
+    config.host = cmd::toggle(&matches, "host", config.host);
+    config.use_daemon = cmd::toggle(&matches, "daemon", config.use_daemon);
+
+    let ctx = cmd::Context {
+        output: if config.json {
+            cmd::Output::Json
+        } else {
+            cmd::Output::Text
+        },
+        config,
+        config_path,
+    };
+    debug!("Context: {:?}", ctx);
+
     // Handle command
     match cmd.as_str() {
-        "m" | "memory" => memory::main(&matches.free)?,
-        "l" | "load" => load::main(&matches.free)?,
-        "c" | "cpu" => cpu::main(&matches.free)?,
-        "t" | "temp" => temp::main(&matches.free)?,
-        "u" | "uptime" => uptime::main(&matches.free)?,
+        "m" | "memory" => cmd::memory::main(&matches.free, &ctx)?,
+        "d" | "disk" => cmd::disk::main(&matches.free, &ctx)?,
+        "l" | "load" => cmd::load::main(&matches.free, &ctx)?,
+        "c" | "cpu" => cmd::cpu::main(&matches.free, &ctx)?,
+        "cpuinfo" => cmd::cpuinfo::main(&matches.free, &ctx)?,
+        "n" | "net" => cmd::net::main(&matches.free, &ctx)?,
+        "t" | "temp" => cmd::temp::main(&matches.free, &ctx)?,
+        "fan" => cmd::fan::main(&matches.free, &ctx)?,
+        "u" | "uptime" => cmd::uptime::main(&matches.free, &ctx)?,
+        "b" | "battery" => cmd::battery::main(&matches.free, &ctx)?,
+        "daemon" => cmd::daemon::main(&matches.free, &ctx)?,
+        "config" => cmd::config::main(&matches.free, &ctx)?,
         "e" | "example" => example::run_all(true),
         _ => {
             return Err(Box::new(Error::new(
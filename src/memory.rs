@@ -1,159 +1,303 @@
-/* Output memory usage info */
-use getopts::Options;
+/* Collect memory usage info */
+use crate::Result;
 use log::debug;
-use std::cmp;
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt,
+    io::{Error, ErrorKind},
+    str::FromStr,
+};
 
-type Result<T = ()> = std::result::Result<T, Box<dyn std::error::Error>>;
-
-#[derive(Debug)]
-struct MemStats {
-    total: usize,
-    used:  usize,
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct MemStats {
+    pub total: usize,
+    pub used:  usize,
 }
 
 impl MemStats {
-    fn new(total: usize, used: usize) -> MemStats {
+    pub fn new(total: usize, used: usize) -> MemStats {
         MemStats { total, used }
     }
+
+    /// Used memory as a percentage of total memory
+    pub fn used_pct(&self) -> f32 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        (self.used as f32 / self.total as f32) * 100.0
+    }
+
+    /// Sum of two stats, e.g. RAM and swap
+    pub fn combine(&self, other: &MemStats) -> MemStats {
+        MemStats::new(self.total + other.total, self.used + other.used)
+    }
 }
 
-fn print_help(command: &str, opts: Options) {
-    let usage = format!("Usage: {} {} [options]", super::PROG, command);
-    print!("{}", opts.usage(&usage));
+/// How used memory is calculated from `/proc/meminfo`
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Accounting {
+    /// `MemTotal - MemFree + Shmem - Buffers - Cached - SReclaimable`, like htop
+    #[default]
+    Htop,
+    /// `MemTotal - MemAvailable`
+    Available,
+    /// `MemTotal - MemFree - Buffers - Cached - SReclaimable`, like `free`
+    Free,
 }
 
-/// Convert bytes into human-readable string format
-///
-/// `si_units`: use 1024 instead of 1000 bytes/kilobyte
-/// `display_byte_suffix`: show 'B' after unit (e.g., 'MB' vs 'M')
-fn humanize_bytes(num: f64, si_units: bool, display_byte_suffix: bool) -> Result<String> {
-    let negative = if num.is_sign_positive() { "" } else { "-" };
-    let num = num.abs();
-    let units = ["", "k", "M", "G", "T", "P", "E", "Z", "Y"];
-    let byte_suffix = if display_byte_suffix { "B" } else { "" };
-    if num < 1_f64 {
-        return Ok(format!("{}{}{}", negative, num, byte_suffix));
+impl FromStr for Accounting {
+    type Err = Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "htop" => Ok(Accounting::Htop),
+            "available" => Ok(Accounting::Available),
+            "free" => Ok(Accounting::Free),
+            _ => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "invalid accounting method `{}` (expected htop, available or free)",
+                    s
+                ),
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Accounting {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Accounting::Htop => "htop",
+            Accounting::Available => "available",
+            Accounting::Free => "free",
+        };
+        write!(f, "{}", s)
     }
-    let delimiter = if si_units { 1024_f64 } else { 1000_f64 };
-    let exponent = cmp::min(
-        (num.ln() / delimiter.ln()).floor() as i32,
-        (units.len() - 1) as i32,
-    );
-    let pretty_bytes = format!("{:.2}", num / delimiter.powi(exponent)).parse::<f64>()? * 1_f64;
-    let unit = units[exponent as usize];
-    let out = format!("{}{:.2}{}{}", negative, pretty_bytes, unit, byte_suffix);
-    Ok(out)
 }
 
 #[cfg(target_os = "linux")]
-fn get_memory() -> Result<MemStats> {
-    use systemstat::{ByteSize, Platform, System};
-
-    let sys = System::new();
-    let mem = sys.memory()?;
-    let meminfo = mem.platform_memory.meminfo;
-    let mem_total = mem.total.as_u64() as usize;
-    let shmem = meminfo
-        .get("Shmem")
-        .cloned()
-        .unwrap_or_else(|| ByteSize::b(0))
-        .as_u64() as usize;
-    let mem_free = meminfo
-        .get("MemFree")
-        .cloned()
-        .unwrap_or_else(|| ByteSize::b(0))
-        .as_u64() as usize;
-    let buffers = meminfo
-        .get("Buffers")
-        .cloned()
-        .unwrap_or_else(|| ByteSize::b(0))
-        .as_u64() as usize;
-    let cached = meminfo
-        .get("Cached")
-        .cloned()
-        .unwrap_or_else(|| ByteSize::b(0))
-        .as_u64() as usize;
-    let s_reclaimable = meminfo
-        .get("SReclaimable")
-        .cloned()
-        .unwrap_or_else(|| ByteSize::b(0))
-        .as_u64() as usize;
-
-    let mem_used = mem_total - mem_free + shmem - buffers - cached - s_reclaimable;
-
-    trace!("{:#?}", System::new().memory()?.platform_memory.meminfo);
-    debug!(
-        "Memory details:
+type MemInfo = std::collections::BTreeMap<String, systemstat::ByteSize>;
+
+#[cfg(target_os = "linux")]
+fn read_meminfo() -> Result<MemInfo> {
+    use log::trace;
+    use systemstat::{Platform, System};
+
+    let meminfo = System::new().memory()?.platform_memory.meminfo;
+    trace!("{:#?}", meminfo);
+    Ok(meminfo)
+}
+
+/// Get `key` from meminfo in bytes, failing if the kernel did not report it
+#[cfg(target_os = "linux")]
+fn meminfo_field(meminfo: &MemInfo, key: &str) -> Result<usize> {
+    meminfo
+        .get(key)
+        .map(|value| value.as_u64() as usize)
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::NotFound,
+                format!("`{}` not found in /proc/meminfo", key),
+            )
+            .into()
+        })
+}
+
+#[cfg(target_os = "linux")]
+pub fn get_memory() -> Result<MemStats> {
+    get_memory_with(Accounting::default())
+}
+
+#[cfg(target_os = "linux")]
+pub fn get_memory_with(method: Accounting) -> Result<MemStats> {
+    let meminfo = read_meminfo()?;
+    let field = |key| meminfo_field(&meminfo, key);
+    let mem_total = field("MemTotal")?;
+
+    // signed so odd kernel values are reported instead of wrapping around
+    let mem_used = match method {
+        Accounting::Htop => {
+            let mem_free = field("MemFree")?;
+            let shmem = field("Shmem")?;
+            let buffers = field("Buffers")?;
+            let cached = field("Cached")?;
+            let s_reclaimable = field("SReclaimable")?;
+            debug!(
+                "Memory details:
         MemTotal:     {}
         MemFree:      {}
         Cached:       {}
         Buffers:      {}
         Shmem:        {}
         SReclaimable: {}",
-        mem_total / 1024,
-        mem_free / 1024,
-        cached / 1024,
-        buffers / 1024,
-        shmem / 1024,
-        s_reclaimable / 1024
-    );
-    Ok(MemStats::new(mem_total, mem_used))
+                mem_total / 1024,
+                mem_free / 1024,
+                cached / 1024,
+                buffers / 1024,
+                shmem / 1024,
+                s_reclaimable / 1024
+            );
+            mem_total as i128 - mem_free as i128 + shmem as i128
+                - buffers as i128
+                - cached as i128
+                - s_reclaimable as i128
+        }
+        Accounting::Available => mem_total as i128 - field("MemAvailable")? as i128,
+        Accounting::Free => {
+            mem_total as i128
+                - field("MemFree")? as i128
+                - field("Buffers")? as i128
+                - field("Cached")? as i128
+                - field("SReclaimable")? as i128
+        }
+    };
+    if mem_used < 0 || mem_used > mem_total as i128 {
+        return Err(Box::new(Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "used memory ({} bytes) out of range using {} accounting",
+                mem_used, method
+            ),
+        )));
+    }
+    Ok(MemStats::new(mem_total, mem_used as usize))
 }
 
-#[cfg(target_os = "macos")]
-fn get_memory() -> Result<MemStats> {
-    let mem_info = sys_info::mem_info()?;
-    debug!("{:#?}", mem_info);
-    Ok(MemStats::new(
-        (mem_info.total * 1024) as usize,
-        ((mem_info.total - mem_info.free - mem_info.avail) * 1024) as usize,
-    ))
+/// Detailed memory usage, in bytes; `None` if not reported by the kernel
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemBreakdown {
+    pub total:          usize,
+    pub used:           usize,
+    pub free:           Option<usize>,
+    pub available:      Option<usize>,
+    pub buffers:        Option<usize>,
+    pub cached:         Option<usize>,
+    pub shmem:          Option<usize>,
+    pub slab_reclaim:   Option<usize>,
+    pub slab_unreclaim: Option<usize>,
+    pub dirty:          Option<usize>,
+    pub writeback:      Option<usize>,
+    pub anon:           Option<usize>,
+    pub mapped:         Option<usize>,
+    pub page_tables:    Option<usize>,
+    pub kernel_stack:   Option<usize>,
 }
 
-pub fn main(args: &[String]) -> Result {
-    debug!("Args: {:?}", args);
-
-    let mut opts = Options::new();
-    opts.optflag("h", "help", "print this help menu");
-    opts.optflag("p", "percent", "used mem as pct of total mem");
-    opts.optflag("u", "used", "show used memory only");
-
-    let matches = opts.parse(&args[1..])?;
-
-    if matches.opt_present("h") {
-        print_help(&args[0], opts);
-        return Ok(());
+impl MemBreakdown {
+    /// Label and value of each field, in display order
+    pub fn rows(&self) -> Vec<(&'static str, Option<usize>)> {
+        vec![
+            ("total", Some(self.total)),
+            ("used", Some(self.used)),
+            ("free", self.free),
+            ("available", self.available),
+            ("buffers", self.buffers),
+            ("cached", self.cached),
+            ("shmem", self.shmem),
+            ("slab_reclaimable", self.slab_reclaim),
+            ("slab_unreclaimable", self.slab_unreclaim),
+            ("dirty", self.dirty),
+            ("writeback", self.writeback),
+            ("anon", self.anon),
+            ("mapped", self.mapped),
+            ("page_tables", self.page_tables),
+            ("kernel_stack", self.kernel_stack),
+        ]
     }
+}
 
-    let used_pct = matches.opt_present("p");
-    debug!("Opt: Used mem as pct: {}", used_pct);
-    let used_only = matches.opt_present("u");
-    debug!("Opt: Used mem only: {}", used_only);
-
-    let stats = get_memory()?;
+/// Break down memory usage; `used` is calculated using `method`
+#[cfg(target_os = "linux")]
+pub fn get_memory_breakdown(method: Accounting) -> Result<MemBreakdown> {
+    let stats = get_memory_with(method)?;
+    let meminfo = read_meminfo()?;
+    let field = |key: &str| meminfo.get(key).map(|value| value.as_u64() as usize);
+    Ok(MemBreakdown {
+        total:          stats.total,
+        used:           stats.used,
+        free:           field("MemFree"),
+        available:      field("MemAvailable"),
+        buffers:        field("Buffers"),
+        cached:         field("Cached"),
+        shmem:          field("Shmem"),
+        slab_reclaim:   field("SReclaimable"),
+        slab_unreclaim: field("SUnreclaim"),
+        dirty:          field("Dirty"),
+        writeback:      field("Writeback"),
+        anon:           field("AnonPages"),
+        mapped:         field("Mapped"),
+        page_tables:    field("PageTables"),
+        kernel_stack:   field("KernelStack"),
+    })
+}
 
+#[cfg(target_os = "linux")]
+pub fn get_swap() -> Result<MemStats> {
+    let meminfo = read_meminfo()?;
+    let swap_total = meminfo_field(&meminfo, "SwapTotal")?;
+    let swap_free = meminfo_field(&meminfo, "SwapFree")?;
     debug!(
-        "Used: {} ({:.2}%)",
-        stats.used,
-        (stats.used as f32 / stats.total as f32) * 100.0
+        "Swap details:
+        SwapTotal:    {}
+        SwapFree:     {}",
+        swap_total / 1024,
+        swap_free / 1024
     );
+    Ok(MemStats::new(
+        swap_total,
+        swap_total.saturating_sub(swap_free),
+    ))
+}
 
-    // Write output
-    if used_pct {
-        println!("{:.1}%", (stats.used as f32 / stats.total as f32) * 100.0);
-        return Ok(());
-    }
-
-    let used_fmt = humanize_bytes(stats.used as f64, true, false)?;
-
-    if used_only {
-        println!("{}", used_fmt);
-        return Ok(());
-    }
+#[cfg(target_os = "macos")]
+pub fn get_memory() -> Result<MemStats> {
+    get_memory_with(Accounting::default())
+}
 
-    let total_fmt = humanize_bytes(stats.total as f64, true, false)?;
+#[cfg(target_os = "macos")]
+pub fn get_memory_with(method: Accounting) -> Result<MemStats> {
+    let mem_info = sys_info::mem_info()?;
+    debug!("{:#?}", mem_info);
+    let unavailable = match method {
+        Accounting::Available => mem_info.avail,
+        Accounting::Htop | Accounting::Free => mem_info.free + mem_info.avail,
+    };
+    Ok(MemStats::new(
+        (mem_info.total * 1024) as usize,
+        (mem_info.total.saturating_sub(unavailable) * 1024) as usize,
+    ))
+}
 
-    println!("{}/{}", used_fmt, total_fmt);
+#[cfg(target_os = "macos")]
+pub fn get_swap() -> Result<MemStats> {
+    let mem_info = sys_info::mem_info()?;
+    Ok(MemStats::new(
+        (mem_info.swap_total * 1024) as usize,
+        ((mem_info.swap_total - mem_info.swap_free) * 1024) as usize,
+    ))
+}
 
-    Ok(())
+#[cfg(target_os = "macos")]
+pub fn get_memory_breakdown(method: Accounting) -> Result<MemBreakdown> {
+    let stats = get_memory_with(method)?;
+    let mem_info = sys_info::mem_info()?;
+    let kb = |n: u64| Some((n * 1024) as usize);
+    Ok(MemBreakdown {
+        total:          stats.total,
+        used:           stats.used,
+        free:           kb(mem_info.free),
+        available:      kb(mem_info.avail),
+        buffers:        kb(mem_info.buffers),
+        cached:         kb(mem_info.cached),
+        shmem:          None,
+        slab_reclaim:   None,
+        slab_unreclaim: None,
+        dirty:          None,
+        writeback:      None,
+        anon:           None,
+        mapped:         None,
+        page_tables:    None,
+        kernel_stack:   None,
+    })
 }
@@ -0,0 +1,131 @@
+/* Collect network interface throughput */
+use crate::{
+    state::{self, Sample},
+    Result,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    io::{Error, ErrorKind},
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use systemstat::{Platform, System};
+
+/// Throughput of an interface over a sampling interval
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NetStats {
+    pub interface: String,
+    /// Bytes per second received
+    pub rx_rate:   f64,
+    /// Bytes per second sent
+    pub tx_rate:   f64,
+    /// Bytes received since boot
+    pub rx_total:  u64,
+    /// Bytes sent since boot
+    pub tx_total:  u64,
+}
+
+/// Cumulative byte counters of an interface at a point in time
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Counters {
+    /// Milliseconds since the unix epoch
+    time_ms: u128,
+    rx:      u64,
+    tx:      u64,
+}
+
+impl Counters {
+    fn read(interface: &str) -> Result<Counters> {
+        let stats = System::new()
+            .network_stats(interface)
+            .map_err(|e| format!("error reading interface `{}`: {}", interface, e))?;
+        Ok(Counters {
+            time_ms: SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis(),
+            rx:      stats.rx_bytes.as_u64(),
+            tx:      stats.tx_bytes.as_u64(),
+        })
+    }
+
+    /// Rates between `prev` and `self`, or `None` if no time has passed
+    fn since(&self, prev: &Counters, interface: &str) -> Option<NetStats> {
+        let elapsed = self.time_ms.checked_sub(prev.time_ms)?;
+        if elapsed == 0 {
+            return None;
+        }
+        let rate = |now: u64, then: u64| now.saturating_sub(then) as f64 * 1000.0 / elapsed as f64;
+        Some(NetStats {
+            interface: interface.to_string(),
+            rx_rate:   rate(self.rx, prev.rx),
+            tx_rate:   rate(self.tx, prev.tx),
+            rx_total:  self.rx,
+            tx_total:  self.tx,
+        })
+    }
+}
+
+impl Sample for Counters {
+    fn to_state(&self) -> String {
+        format!("{} {} {}", self.time_ms, self.rx, self.tx)
+    }
+
+    fn from_state(s: &str) -> Option<Self> {
+        let mut fields = s.split_whitespace().map(str::parse::<u128>);
+        Some(Counters {
+            time_ms: fields.next()?.ok()?,
+            rx:      fields.next()?.ok()? as u64,
+            tx:      fields.next()?.ok()? as u64,
+        })
+    }
+
+    fn settled_since(&self, prev: &Self) -> bool {
+        // shorter than this since the saved sample is too noisy to report
+        const MIN_ELAPSED_MS: u128 = 250;
+
+        self.time_ms.saturating_sub(prev.time_ms) >= MIN_ELAPSED_MS
+    }
+}
+
+/// Name of the interface used by the default route
+#[cfg(target_os = "linux")]
+pub fn default_interface() -> Result<String> {
+    let routes = std::fs::read_to_string("/proc/net/route")?;
+    routes
+        .lines()
+        .skip(1)
+        .map(|line| line.split_whitespace().collect::<Vec<_>>())
+        .find(|fields| fields.get(1) == Some(&"00000000"))
+        .and_then(|fields| fields.first().map(|iface| iface.to_string()))
+        .ok_or_else(|| Error::new(ErrorKind::NotFound, "no default route found").into())
+}
+
+#[cfg(target_os = "macos")]
+pub fn default_interface() -> Result<String> {
+    Err(Box::new(Error::new(
+        ErrorKind::Other,
+        "default interface is not supported on macos; specify an interface",
+    )))
+}
+
+/// Sample throughput of `interface` over `interval`
+pub fn get_net(interface: &str, interval: Duration) -> Result<NetStats> {
+    let start = Counters::read(interface)?;
+    thread::sleep(interval);
+    let end = Counters::read(interface)?;
+    end.since(&start, interface).ok_or_else(|| {
+        Error::new(ErrorKind::InvalidData, "sampling interval too short").into()
+    })
+}
+
+/// Throughput since the counters saved by the previous call, without
+/// sleeping (see `state::since_last`)
+pub fn get_net_since_last(
+    interface: &str,
+    max_age: Duration,
+    fallback: Duration,
+) -> Result<NetStats> {
+    let name = format!("net-{}", interface);
+    let (now, prev) = state::since_last(&name, || Counters::read(interface), max_age, fallback)?;
+    now.since(&prev, interface).ok_or_else(|| {
+        Error::new(ErrorKind::InvalidData, "sampling interval too short").into()
+    })
+}
@@ -0,0 +1,132 @@
+/* Persist small samples between invocations */
+use crate::Result;
+use log::{debug, warn};
+use std::{
+    env, fs,
+    io::{Error, ErrorKind},
+    os::unix::fs::{DirBuilderExt, MetadataExt},
+    path::PathBuf,
+    thread,
+    time::{Duration, SystemTime},
+};
+
+/// Sample length used by `since_last` callers when there is no usable state
+pub const FALLBACK_SAMPLE: Duration = Duration::from_millis(250);
+
+/// Counters that can be saved between invocations and compared later
+pub trait Sample: Sized {
+    /// Contents of the state file
+    fn to_state(&self) -> String;
+    /// Parse what `to_state` wrote
+    fn from_state(s: &str) -> Option<Self>;
+    /// Whether enough time passed since `prev` for a reading that is not
+    /// too noisy to report
+    fn settled_since(&self, prev: &Self) -> bool;
+}
+
+/// Per-user directory for saved state
+///
+/// Uses `$XDG_RUNTIME_DIR/sysinfo` if set, otherwise a user-specific
+/// directory under the system temp dir.
+pub fn state_dir() -> PathBuf {
+    match env::var_os("XDG_RUNTIME_DIR") {
+        Some(dir) => PathBuf::from(dir).join("sysinfo"),
+        None => {
+            let user = env::var("USER").unwrap_or_else(|_| String::from("default"));
+            env::temp_dir().join(format!("sysinfo-{}", user))
+        }
+    }
+}
+
+/// `state_dir()`, created private (mode 0700) if missing
+///
+/// The temp dir is shared with other users, so the directory is refused
+/// unless it is a real directory owned by the current user.
+pub fn private_dir() -> Result<PathBuf> {
+    let dir = state_dir();
+    if let Err(e) = fs::DirBuilder::new().recursive(true).mode(0o700).create(&dir) {
+        // recursive creation only fails on an existing non-directory
+        if e.kind() != ErrorKind::AlreadyExists {
+            return Err(e.into());
+        }
+    }
+    owned_dir()
+}
+
+/// `state_dir()` if it exists and is owned by the current user
+///
+/// Unlike `private_dir`, nothing is created, so readers leave no trace.
+pub fn owned_dir() -> Result<PathBuf> {
+    let dir = state_dir();
+    let meta = fs::symlink_metadata(&dir)?;
+    // SAFETY: geteuid has no preconditions and cannot fail
+    let uid = unsafe { libc::geteuid() };
+    if !meta.is_dir() || meta.uid() != uid {
+        return Err(Box::new(Error::new(
+            ErrorKind::PermissionDenied,
+            format!("state dir {:?} is not a directory owned by the current user", dir),
+        )));
+    }
+    Ok(dir)
+}
+
+/// Read saved state `name`, unless it was written more than `max_age` ago
+pub fn load(name: &str, max_age: Duration) -> Option<String> {
+    let path = owned_dir()
+        .map_err(|e| debug!("Not loading state: {}", e))
+        .ok()?
+        .join(name);
+    let modified = fs::metadata(&path).and_then(|m| m.modified()).ok()?;
+    let age = SystemTime::now().duration_since(modified).ok()?;
+    if age > max_age {
+        debug!("State {:?} is stale ({:?} old)", path, age);
+        return None;
+    }
+    fs::read_to_string(&path).ok()
+}
+
+/// Save state `name`, replacing any previous contents
+pub fn save(name: &str, contents: &str) -> Result {
+    let dir = private_dir()?;
+    // write then rename so concurrent readers never see a partial file
+    let tmp = dir.join(format!(".{}.{}", name, std::process::id()));
+    fs::write(&tmp, contents)?;
+    fs::rename(&tmp, dir.join(name))?;
+    Ok(())
+}
+
+/// Pair a sample from `read` with the one saved in state `name` by the
+/// previous call, as `(now, prev)`, without sleeping
+///
+/// If there is no settled sample newer than `max_age`, sample over `fallback`
+/// instead. The latest sample is saved for the next call either way.
+pub fn since_last<T, F>(
+    name: &str,
+    read: F,
+    max_age: Duration,
+    fallback: Duration,
+) -> Result<(T, T)>
+where
+    T: Sample + std::fmt::Debug,
+    F: Fn() -> Result<T>,
+{
+    let store = |sample: &T| {
+        if let Err(e) = save(name, &sample.to_state()) {
+            warn!("Error saving state {}: {}", name, e);
+        }
+    };
+
+    let now = read()?;
+    let prev = load(name, max_age)
+        .and_then(|s| T::from_state(&s))
+        .filter(|prev| now.settled_since(prev));
+    debug!("Saved state {}: {:?}", name, prev);
+    if let Some(prev) = prev {
+        store(&now);
+        return Ok((now, prev));
+    }
+    thread::sleep(fallback);
+    let end = read()?;
+    store(&end);
+    Ok((end, now))
+}
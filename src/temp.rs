@@ -1,41 +1,392 @@
-/* Output cpu info */
-use getopts::Options;
-use std::io::{self, Write};
+/* Collect cpu temperature info */
+use crate::{
+    hwmon::{self, read, HWMON},
+    Result,
+};
+use log::debug;
+use serde::{Deserialize, Serialize};
+use std::{
+    convert::TryFrom,
+    env, fmt,
+    io::{Error, ErrorKind},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
 use systemstat::{Platform, System};
 
-fn print_help(command: &str, opts: Options) {
-    let usage = format!("Usage: {} {} [options]", super::PROG, command);
-    print!("{}", opts.usage(&usage));
+/// Where the kernel exposes thermal zones
+pub const THERMAL: &str = "/sys/class/thermal";
+
+/// Thermal zone types that measure a cpu package
+const CPU_ZONES: &[&str] = &["x86_pkg_temp", "cpu-thermal", "cpu_thermal", "soc_thermal"];
+
+/// Cpu temperature, stored in degrees celsius
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Temp {
+    #[serde(alias = "celcius")]
+    pub celsius: f32,
+}
+
+impl Temp {
+    pub fn fahrenheit(&self) -> f32 {
+        (self.celsius * 9.0) / 5.0 + 32.0
+    }
+
+    pub fn kelvin(&self) -> f32 {
+        self.celsius + 273.15
+    }
+
+    /// Temperature in degrees (or kelvin) of `unit`
+    pub fn value(&self, unit: Unit) -> f32 {
+        match unit {
+            Unit::Celsius => self.celsius,
+            Unit::Fahrenheit => self.fahrenheit(),
+            Unit::Kelvin => self.kelvin(),
+        }
+    }
+}
+
+/// Temperature scale to report
+///
+/// Parsed from `c`, `f`, `k` or `auto`, which picks one from the locale.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase", try_from = "String")]
+pub enum Unit {
+    Celsius,
+    Fahrenheit,
+    Kelvin,
+}
+
+/// Territories (ISO 3166 codes) that measure temperature in fahrenheit
+const FAHRENHEIT_REGIONS: &[&str] = &["US", "LR", "BS", "BZ", "KY", "PW", "FM", "MH"];
+
+impl Unit {
+    /// Suffix appended to values, e.g. `º` in `72º`
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Unit::Celsius | Unit::Fahrenheit => "º",
+            Unit::Kelvin => "K",
+        }
+    }
+
+    /// Unit customary in the locale from `LC_ALL`, `LC_MEASUREMENT` or `LANG`
+    pub fn from_locale() -> Unit {
+        let locale = ["LC_ALL", "LC_MEASUREMENT", "LANG"]
+            .iter()
+            .filter_map(|var| env::var(var).ok())
+            .find(|locale| !locale.is_empty())
+            .unwrap_or_default();
+        Unit::for_locale(&locale)
+    }
+
+    /// Unit customary in `locale`, e.g. `en_US.UTF-8`; celsius if unknown
+    fn for_locale(locale: &str) -> Unit {
+        let region = locale
+            .split(['.', '@'])
+            .next()
+            .and_then(|name| name.split('_').nth(1));
+        match region {
+            Some(region) if FAHRENHEIT_REGIONS.contains(&region) => Unit::Fahrenheit,
+            _ => Unit::Celsius,
+        }
+    }
 }
 
-pub fn main(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
-    log::debug!("Args: {:?}", args);
+impl FromStr for Unit {
+    type Err = Error;
 
-    let mut opts = Options::new();
-    opts.optflag("h", "help", "print this help menu");
-    opts.optflag(
-        "c",
-        "celcius",
-        "show result in degrees celcius (not fahrenheit)",
-    );
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "c" | "celsius" | "celcius" => Ok(Unit::Celsius),
+            "f" | "fahrenheit" => Ok(Unit::Fahrenheit),
+            "k" | "kelvin" => Ok(Unit::Kelvin),
+            "auto" => Ok(Unit::from_locale()),
+            _ => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("invalid temperature unit `{}` (expected C, F, K or auto)", s),
+            )),
+        }
+    }
+}
 
-    let matches = opts.parse(&args[1..])?;
+impl TryFrom<String> for Unit {
+    type Error = Error;
 
-    if matches.opt_present("h") {
-        print_help(&args[0], opts);
-        return Ok(());
+    fn try_from(s: String) -> std::result::Result<Self, Self::Error> {
+        s.parse()
     }
+}
+
+impl fmt::Display for Unit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Unit::Celsius => "celsius",
+            Unit::Fahrenheit => "fahrenheit",
+            Unit::Kelvin => "kelvin",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// Kernel interface a sensor is read from
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Source {
+    /// `/sys/class/thermal`
+    Thermal,
+    /// `/sys/class/hwmon`
+    Hwmon,
+}
+
+/// A temperature sensor of a thermal zone or hwmon chip
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Sensor {
+    /// Unique name, e.g. `thermal_zone0` or `hwmon3/temp1`
+    pub name:    String,
+    /// Zone type or hwmon label, e.g. `x86_pkg_temp` or `Package id 0`
+    pub label:   String,
+    pub source:  Source,
+    /// Whether the sensor measures a whole cpu package
+    pub cpu:     bool,
+    pub celsius: f32,
+}
+
+impl Sensor {
+    pub fn temp(&self) -> Temp {
+        Temp {
+            celsius: self.celsius,
+        }
+    }
+}
+
+/// How readings of several cpu sensors are combined
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Aggregate {
+    /// Hottest sensor
+    #[default]
+    Max,
+    /// Mean of all sensors
+    Avg,
+}
 
-    let sys = System::new();
-    let temp = sys.cpu_temp()?;
+impl FromStr for Aggregate {
+    type Err = Error;
 
-    let stdout = io::stdout();
-    let mut cout = stdout.lock();
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "max" => Ok(Aggregate::Max),
+            "avg" => Ok(Aggregate::Avg),
+            _ => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("invalid aggregation `{}` (expected max or avg)", s),
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Aggregate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Aggregate::Max => "max",
+            Aggregate::Avg => "avg",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// Millidegrees in `path` as degrees celsius
+fn read_celsius(path: PathBuf) -> Option<f32> {
+    read(path)?.parse::<f32>().ok().map(|milli| milli / 1000.0)
+}
+
+/// All sensors of thermal zones under `thermal` and hwmon chips under `hwmon`
+pub fn get_sensors_from<P: AsRef<Path>, Q: AsRef<Path>>(thermal: P, hwmon: Q) -> Vec<Sensor> {
+    let mut sensors = Vec::new();
+    for zone in hwmon::entries(thermal.as_ref(), "thermal_zone") {
+        let label = read(zone.join("type")).unwrap_or_default();
+        if let Some(celsius) = read_celsius(zone.join("temp")) {
+            sensors.push(Sensor {
+                name: zone.file_name().unwrap_or_default().to_string_lossy().into_owned(),
+                source: Source::Thermal,
+                cpu: CPU_ZONES.contains(&label.as_str()),
+                label,
+                celsius,
+            });
+        }
+    }
+    for input in hwmon::inputs(hwmon.as_ref(), "temp") {
+        let label = input.label.clone().unwrap_or_else(|| input.chip.clone());
+        sensors.push(Sensor {
+            name: format!("{}/{}", input.dir, input.sensor),
+            source: Source::Hwmon,
+            cpu: match input.chip.as_str() {
+                "coretemp" => label.starts_with("Package id"),
+                "k10temp" | "zenpower" => label == "Tctl" || label == "Tdie",
+                "cpu_thermal" => true,
+                _ => false,
+            },
+            label,
+            celsius: input.value as f32 / 1000.0,
+        });
+    }
+    debug!("Temperature sensors: {:#?}", sensors);
+    sensors
+}
+
+/// All thermal zone and hwmon sensors
+pub fn get_sensors() -> Vec<Sensor> {
+    get_sensors_from(THERMAL, HWMON)
+}
+
+/// Sensor whose name or label is `name`
+pub fn get_sensor(name: &str) -> Result<Sensor> {
+    get_sensors()
+        .into_iter()
+        .find(|sensor| sensor.name == name || sensor.label == name)
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::NotFound,
+                format!("no temperature sensor named `{}` found", name),
+            )
+            .into()
+        })
+}
+
+/// Combine the cpu package sensors among `sensors` using `aggregate`
+///
+/// Hwmon chips are preferred over thermal zones, since both usually report
+/// the same package. Returns `None` without any cpu sensor.
+fn cpu_temp(sensors: &[Sensor], aggregate: Aggregate) -> Option<Temp> {
+    let (hwmon, zones): (Vec<_>, Vec<_>) = sensors
+        .iter()
+        .filter(|sensor| sensor.cpu)
+        .partition(|sensor| sensor.source == Source::Hwmon);
+    let cpu = if hwmon.is_empty() { zones } else { hwmon };
+    if cpu.is_empty() {
+        return None;
+    }
+    let readings = cpu.iter().map(|sensor| sensor.celsius);
+    let celsius = match aggregate {
+        Aggregate::Max => readings.fold(f32::MIN, f32::max),
+        Aggregate::Avg => readings.sum::<f32>() / cpu.len() as f32,
+    };
+    Some(Temp { celsius })
+}
+
+/// Combine all cpu package sensors using `aggregate`
+///
+/// Without any cpu sensor, the first thermal zone is used.
+pub fn get_temp_with(aggregate: Aggregate) -> Result<Temp> {
+    if let Some(temp) = cpu_temp(&get_sensors(), aggregate) {
+        return Ok(temp);
+    }
+    let celsius = System::new().cpu_temp().map_err(|e| {
+        debug!("Error reading fallback thermal zone: {}", e);
+        Error::new(ErrorKind::NotFound, "no temperature sensors found")
+    })?;
+    Ok(Temp { celsius })
+}
+
+/// Temperature of the hottest cpu package
+pub fn get_temp() -> Result<Temp> {
+    get_temp_with(Aggregate::Max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::{fixture_dir, write_fixture};
+    use std::fs;
+
+    /// Thermal zone and duplicate `coretemp` chips as found on dual-socket boards
+    fn fixtures(test: &str) -> (PathBuf, PathBuf) {
+        let root = fixture_dir(&format!("temp-{}", test));
+        let (thermal, hwmon) = (root.join("thermal"), root.join("hwmon"));
+        write_fixture(&thermal.join("thermal_zone0"), &[
+            ("type", "x86_pkg_temp"),
+            ("temp", "90000"),
+        ]);
+        write_fixture(&thermal.join("thermal_zone1"), &[("type", "acpitz"), ("temp", "30000")]);
+        write_fixture(&hwmon.join("hwmon0"), &[
+            ("name", "coretemp"),
+            ("temp1_input", "50000"),
+            ("temp1_label", "Package id 0"),
+            ("temp2_input", "45000"),
+            ("temp2_label", "Core 0"),
+        ]);
+        write_fixture(&hwmon.join("hwmon1"), &[
+            ("name", "coretemp"),
+            ("temp1_input", "60000"),
+            ("temp1_label", "Package id 1"),
+        ]);
+        write_fixture(&hwmon.join("hwmon2"), &[("name", "nvme"), ("temp1_input", "40000")]);
+        (thermal, hwmon)
+    }
+
+    #[test]
+    fn sensors() {
+        let (thermal, hwmon) = fixtures("sensors");
+        let sensors = get_sensors_from(&thermal, &hwmon);
+        let summary: Vec<_> = sensors
+            .iter()
+            .map(|s| (s.name.as_str(), s.label.as_str(), s.source, s.cpu, s.celsius))
+            .collect();
+        assert_eq!(summary, [
+            ("thermal_zone0", "x86_pkg_temp", Source::Thermal, true, 90.0),
+            ("thermal_zone1", "acpitz", Source::Thermal, false, 30.0),
+            ("hwmon0/temp1", "Package id 0", Source::Hwmon, true, 50.0),
+            ("hwmon0/temp2", "Core 0", Source::Hwmon, false, 45.0),
+            ("hwmon1/temp1", "Package id 1", Source::Hwmon, true, 60.0),
+            // unlabelled inputs fall back to the chip name
+            ("hwmon2/temp1", "nvme", Source::Hwmon, false, 40.0),
+        ]);
+        fs::remove_dir_all(thermal.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn cpu_temps() {
+        let (thermal, hwmon) = fixtures("cpu");
+        let sensors = get_sensors_from(&thermal, &hwmon);
+        // the hotter thermal zone is ignored in favour of hwmon packages
+        assert_eq!(cpu_temp(&sensors, Aggregate::Max).unwrap().celsius, 60.0);
+        assert_eq!(cpu_temp(&sensors, Aggregate::Avg).unwrap().celsius, 55.0);
+
+        let zones = get_sensors_from(&thermal, thermal.join("missing"));
+        assert_eq!(cpu_temp(&zones, Aggregate::Max).unwrap().celsius, 90.0);
+        assert!(cpu_temp(&zones[1..], Aggregate::Max).is_none());
+        assert!(cpu_temp(&[], Aggregate::Max).is_none());
+        fs::remove_dir_all(thermal.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn units() {
+        let cases = [
+            ("C", Unit::Celsius),
+            ("celsius", Unit::Celsius),
+            ("celcius", Unit::Celsius),
+            ("f", Unit::Fahrenheit),
+            ("Kelvin", Unit::Kelvin),
+        ];
+        for (s, unit) in cases.iter() {
+            assert_eq!(s.parse::<Unit>().unwrap(), *unit, "{}", s);
+        }
+        assert!("x".parse::<Unit>().is_err());
+        assert_eq!(Unit::Celsius.to_string(), "celsius");
+    }
 
-    if matches.opt_present("c") {
-        write!(cout, "{:.0}º", temp)?;
-    } else {
-        write!(cout, "{:.0}º", ((temp * 9.0) / 5.0 + 32.0))?;
+    #[test]
+    fn locale_units() {
+        let cases = [
+            ("en_US.UTF-8", Unit::Fahrenheit),
+            ("en_LR", Unit::Fahrenheit),
+            ("en_GB.UTF-8", Unit::Celsius),
+            ("de_DE@euro", Unit::Celsius),
+            ("C", Unit::Celsius),
+            ("", Unit::Celsius),
+        ];
+        for (locale, unit) in cases.iter() {
+            assert_eq!(Unit::for_locale(locale), *unit, "{}", locale);
+        }
     }
-    Ok(())
 }
@@ -0,0 +1,170 @@
+//! Templates are plain text with `{name}` or `{name:spec}` placeholders.
+//! `spec` is `[width][.precision][h]`, where `h` renders the value as
+//! human-readable bytes (e.g., `{used:h}` -> `1.50G`, `{used:.1h}` -> `1.5G`).
+//! Use `{{` and `}}` for literal braces.
+use crate::{util::humanize_bytes_with, Result};
+use std::io::{Error, ErrorKind};
+
+/// A named value that can be substituted into a template
+#[derive(Debug, Clone)]
+pub enum Value {
+    Int(u64),
+    /// Printed with 1 decimal place unless a precision is given
+    Float(f64),
+    Text(String),
+}
+
+#[derive(Debug)]
+struct Spec {
+    width:     Option<usize>,
+    precision: Option<usize>,
+    human:     bool,
+}
+
+fn invalid(msg: String) -> Box<dyn std::error::Error> {
+    Box::new(Error::new(ErrorKind::InvalidInput, msg))
+}
+
+impl Spec {
+    fn parse(spec: &str) -> Result<Spec> {
+        let err = || invalid(format!("invalid format spec `{}`", spec));
+        let (rest, human) = match spec.strip_suffix('h') {
+            Some(rest) => (rest, true),
+            None => (spec, false),
+        };
+        let (width, precision) = match rest.find('.') {
+            Some(i) => (&rest[..i], Some(&rest[i + 1..])),
+            None => (rest, None),
+        };
+        Ok(Spec {
+            width: match width {
+                "" => None,
+                w => Some(w.parse().map_err(|_| err())?),
+            },
+            precision: match precision {
+                Some(p) => Some(p.parse().map_err(|_| err())?),
+                None => None,
+            },
+            human,
+        })
+    }
+}
+
+impl Value {
+    fn format(&self, spec: &Spec) -> Result<String> {
+        let human = |n: f64| humanize_bytes_with(n, true, false, spec.precision.unwrap_or(2));
+        let out = match (self, spec.human) {
+            (Value::Int(n), true) => human(*n as f64)?,
+            (Value::Float(n), true) => human(*n)?,
+            (Value::Int(n), false) => match spec.precision {
+                Some(p) => format!("{:.*}", p, *n as f64),
+                None => n.to_string(),
+            },
+            (Value::Float(n), false) => format!("{:.*}", spec.precision.unwrap_or(1), n),
+            (Value::Text(s), _) => s.clone(),
+        };
+        Ok(match spec.width {
+            Some(w) => format!("{:>w$}", out, w = w),
+            None => out,
+        })
+    }
+}
+
+/// Substitute `fields` into `template`
+pub fn render(template: &str, fields: &[(&str, Value)]) -> Result<String> {
+    let mut out = String::new();
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                out.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                out.push('}');
+            }
+            '{' => {
+                let mut placeholder = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => placeholder.push(c),
+                        None => return Err(invalid(format!("unclosed `{{` in `{}`", template))),
+                    }
+                }
+                let (name, spec) = match placeholder.find(':') {
+                    Some(i) => (&placeholder[..i], &placeholder[i + 1..]),
+                    None => (placeholder.as_str(), ""),
+                };
+                let value = fields
+                    .iter()
+                    .find(|(n, _)| *n == name)
+                    .map(|(_, v)| v)
+                    .ok_or_else(|| {
+                        let names: Vec<&str> = fields.iter().map(|(n, _)| *n).collect();
+                        invalid(format!(
+                            "unknown field `{}` (available: {})",
+                            name,
+                            names.join(", ")
+                        ))
+                    })?;
+                out.push_str(&value.format(&Spec::parse(spec)?)?);
+            }
+            '}' => return Err(invalid(format!("unmatched `}}` in `{}`", template))),
+            c => out.push(c),
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields() -> Vec<(&'static str, Value)> {
+        vec![
+            ("used", Value::Int(1_610_612_736)),
+            ("pct", Value::Float(42.36)),
+            ("name", Value::Text(String::from("BAT0"))),
+        ]
+    }
+
+    #[test]
+    fn renders() {
+        let cases = [
+            ("plain text", "plain text"),
+            ("{{literal}} {name}", "{literal} BAT0"),
+            ("}}{{", "}{"),
+            ("{pct}%", "42.4%"),
+            ("{pct:.0}%", "42%"),
+            ("{pct:.2}%", "42.36%"),
+            ("[{pct:6.1}]", "[  42.4]"),
+            ("[{name:6}]", "[  BAT0]"),
+            ("{used}", "1610612736"),
+            ("{used:.1}", "1610612736.0"),
+            ("{used:h}", "1.50G"),
+            ("{used:.1h}", "1.5G"),
+            ("{used:.0h}", "2G"),
+            ("[{used:6.1h}]", "[  1.5G]"),
+        ];
+        for (template, expected) in cases.iter() {
+            assert_eq!(&render(template, &fields()).unwrap(), expected, "{}", template);
+        }
+    }
+
+    #[test]
+    fn rejects() {
+        let cases = [
+            ("{missing}", "unknown field `missing` (available: used, pct, name)"),
+            ("{used", "unclosed `{` in `{used`"),
+            ("used}", "unmatched `}` in `used}`"),
+            ("{used:x}", "invalid format spec `x`"),
+            ("{used:.h}", "invalid format spec `.h`"),
+        ];
+        for (template, expected) in cases.iter() {
+            let err = render(template, &fields()).unwrap_err();
+            assert_eq!(&err.to_string(), expected, "{}", template);
+        }
+    }
+}
@@ -1,74 +1,374 @@
-/* Output system uptime */
-use getopts::Options;
+/* Collect system uptime */
+use crate::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 use std::{
-    io::{self, Write},
-    ops::Sub,
+    fmt,
+    io::{Error, ErrorKind},
+    str::FromStr,
 };
 use systemstat::{Platform, System};
 
-fn print_help(command: &str, opts: Options) {
-    const DESC: &str = "Print uptime in specified format.";
-    let usage = format!("Usage: {} {} [options]\n\n{}", super::PROG, command, DESC);
-    print!("{}", opts.usage(&usage));
+/// How uptime is written out
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Style {
+    /// e.g. `3d4h`
+    #[default]
+    Compact,
+    /// e.g. `3 days, 4 hours`
+    Long,
+    /// e.g. `3d 04:12`
+    Clock,
 }
 
-pub fn main(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
-    log::debug!("Args: {:?}", args);
+impl FromStr for Style {
+    type Err = Error;
 
-    let mut opts = Options::new();
-    opts.optflag("h", "help", "print this help menu");
-    opts.optflag("w", "weeks", "format as weeks instead of days");
-    opts.optflag(
-        "p",
-        "precise",
-        "show hours (if < 1 week) or minutes (if < 1 day)",
-    );
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "compact" => Ok(Style::Compact),
+            "long" => Ok(Style::Long),
+            "clock" => Ok(Style::Clock),
+            _ => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "invalid uptime style `{}` (expected compact, long or clock)",
+                    s
+                ),
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Style {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Style::Compact => "compact",
+            Style::Long => "long",
+            Style::Clock => "clock",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// Uptime split into units, e.g. for `3d4h`
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Parts {
+    /// Always 0 unless split with weeks
+    pub weeks:   u64,
+    /// Days of the current week when split with weeks, otherwise all days
+    pub days:    u64,
+    pub hours:   u64,
+    pub minutes: u64,
+    pub seconds: u64,
+}
+
+impl Parts {
+    pub fn new(secs: u64, weeks: bool) -> Parts {
+        let days = secs / 86_400;
+        Parts {
+            weeks:   if weeks { days / 7 } else { 0 },
+            days:    if weeks { days % 7 } else { days },
+            hours:   secs % 86_400 / 3_600,
+            minutes: secs % 3_600 / 60,
+            seconds: secs % 60,
+        }
+    }
+
+    /// Units to show with their compact and long names, largest first
+    ///
+    /// Shows units down to days, or to the largest nonzero unit if that is
+    /// smaller. `precise` adds one more unit and `seconds` shows all down to
+    /// seconds. Zero units are left out, but at least one unit is returned.
+    fn units(&self, precise: bool, seconds: bool) -> Vec<(u64, &'static str, &'static str)> {
+        let all = [
+            (self.weeks, "w", "week"),
+            (self.days, "d", "day"),
+            (self.hours, "h", "hour"),
+            (self.minutes, "m", "minute"),
+            (self.seconds, "s", "second"),
+        ];
+        const DAYS: usize = 1;
+        const MINUTES: usize = 3;
+        const SECONDS: usize = 4;
+        let lead = all[..SECONDS]
+            .iter()
+            .position(|(n, _, _)| *n > 0)
+            .unwrap_or(MINUTES);
+        let last = if seconds {
+            SECONDS
+        } else if precise {
+            (lead.max(DAYS) + 1).min(MINUTES)
+        } else {
+            lead.max(DAYS)
+        };
+        let shown = all[..=last]
+            .iter()
+            .filter(|(n, _, _)| *n > 0)
+            .cloned()
+            .collect::<Vec<_>>();
+        if shown.is_empty() {
+            vec![all[last]]
+        } else {
+            shown
+        }
+    }
+
+    /// e.g. `3d4h`
+    pub fn compact(&self, precise: bool, seconds: bool) -> String {
+        self.units(precise, seconds)
+            .iter()
+            .map(|(n, unit, _)| format!("{}{}", n, unit))
+            .collect()
+    }
+
+    /// e.g. `3 days, 4 hours`
+    pub fn long(&self, precise: bool, seconds: bool) -> String {
+        self.units(precise, seconds)
+            .iter()
+            .map(|(n, _, unit)| format!("{} {}{}", n, unit, if *n == 1 { "" } else { "s" }))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// e.g. `3d 04:12`, or `04:12:09` with `seconds`
+    pub fn clock(&self, seconds: bool) -> String {
+        let mut parts = Vec::new();
+        if self.weeks > 0 {
+            parts.push(format!("{}w", self.weeks));
+        }
+        if self.days > 0 {
+            parts.push(format!("{}d", self.days));
+        }
+        let mut time = format!("{:02}:{:02}", self.hours, self.minutes);
+        if seconds {
+            time += &format!(":{:02}", self.seconds);
+        }
+        parts.push(time);
+        parts.join(" ")
+    }
+}
+
+/// Time elapsed since boot
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Uptime {
+    pub seconds: u64,
+}
+
+impl Uptime {
+    pub fn duration(&self) -> chrono::Duration {
+        chrono::Duration::seconds(self.seconds as i64)
+    }
+
+    pub fn parts(&self, weeks: bool) -> Parts {
+        Parts::new(self.seconds, weeks)
+    }
 
-    let matches = opts.parse(&args[1..])?;
-    if matches.opt_present("h") {
-        print_help(&args[0], opts);
-        return Ok(());
+    /// Expand `%`-sequences of `template`, like `strftime` for durations
+    ///
+    /// `%w` weeks, `%d` days, `%D` days of the current week, `%H`, `%M` and
+    /// `%S` zero-padded hours, minutes and seconds of the current day, `%s`
+    /// total seconds and `%%` a literal `%`.
+    pub fn strftime(&self, template: &str) -> Result<String> {
+        let secs = self.seconds;
+        let mut out = String::new();
+        let mut chars = template.chars();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                out.push(c);
+                continue;
+            }
+            let value = match chars.next() {
+                Some('w') => (secs / 604_800).to_string(),
+                Some('d') => (secs / 86_400).to_string(),
+                Some('D') => (secs % 604_800 / 86_400).to_string(),
+                Some('H') => format!("{:02}", secs % 86_400 / 3_600),
+                Some('M') => format!("{:02}", secs % 3_600 / 60),
+                Some('S') => format!("{:02}", secs % 60),
+                Some('s') => secs.to_string(),
+                Some('%') => String::from("%"),
+                Some(c) => {
+                    return Err(Box::new(Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("unknown uptime format sequence `%{}`", c),
+                    )))
+                }
+                None => {
+                    return Err(Box::new(Error::new(
+                        ErrorKind::InvalidInput,
+                        "uptime format ends with `%`",
+                    )))
+                }
+            };
+            out.push_str(&value);
+        }
+        Ok(out)
     }
+}
 
+pub fn get_uptime() -> Result<Uptime> {
     let sys = System::new();
-    let uptime = sys.uptime()?;
-    let duration = chrono::Duration::from_std(uptime)?;
-    let stdout = io::stdout();
-    let mut cout = stdout.lock();
-    write!(cout, "↑")?;
-    // push weeks
-    let fmt_weeks = matches.opt_present("weeks");
-    if duration.num_weeks() > 0 && fmt_weeks {
-        write!(cout, "{}w", duration.num_weeks())?;
-    }
-    // push days
-    let days = if fmt_weeks {
-        duration
-            .sub(chrono::Duration::weeks(duration.num_weeks()))
-            .num_days()
-    } else {
-        duration.num_days()
-    };
-
-    if days > 0 {
-        write!(cout, "{}d", days)?;
-    }
-    let hours = duration
-        .sub(chrono::Duration::days(duration.num_days()))
-        .num_hours();
-    // push hours if < 1 day OR if --precise < 1 week
-    if duration.num_days() < 1 && hours > 0
-        || (duration.num_weeks() == 0 && hours > 0 && matches.opt_present("precise"))
-    {
-        write!(cout, "{}h", hours)?;
-    }
-    // push minutes if < 1 hour OR if --precise and < 1 day
-    if duration.num_days() < 1 && matches.opt_present("precise") {
-        let minutes = duration
-            .sub(chrono::Duration::hours(duration.num_hours()))
-            .num_minutes();
-        write!(cout, "{}m", minutes)?;
-    }
-    writeln!(cout)?;
-    Ok(())
+    Ok(Uptime {
+        seconds: sys.uptime()?.as_secs(),
+    })
+}
+
+/// When the system was booted
+pub fn get_boot_time() -> Result<DateTime<Utc>> {
+    Ok(System::new().boot_time()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINUTE: u64 = 60;
+    const HOUR: u64 = 60 * MINUTE;
+    const DAY: u64 = 24 * HOUR;
+    const WEEK: u64 = 7 * DAY;
+
+    fn parts(weeks: u64, days: u64, hours: u64, minutes: u64, seconds: u64) -> Parts {
+        Parts {
+            weeks,
+            days,
+            hours,
+            minutes,
+            seconds,
+        }
+    }
+
+    #[test]
+    fn split() {
+        let cases = [
+            (0, false, parts(0, 0, 0, 0, 0)),
+            (59, false, parts(0, 0, 0, 0, 59)),
+            (MINUTE, false, parts(0, 0, 0, 1, 0)),
+            (HOUR - 1, false, parts(0, 0, 0, 59, 59)),
+            (HOUR, false, parts(0, 0, 1, 0, 0)),
+            (DAY - 1, false, parts(0, 0, 23, 59, 59)),
+            (DAY, false, parts(0, 1, 0, 0, 0)),
+            (3 * DAY + 4 * HOUR + 12 * MINUTE, false, parts(0, 3, 4, 12, 0)),
+            (WEEK, false, parts(0, 7, 0, 0, 0)),
+            (WEEK, true, parts(1, 0, 0, 0, 0)),
+            (WEEK - 1, true, parts(0, 6, 23, 59, 59)),
+            (2 * WEEK + 3 * DAY + 5 * HOUR, true, parts(2, 3, 5, 0, 0)),
+            (2 * WEEK + 3 * DAY + 5 * HOUR, false, parts(0, 17, 5, 0, 0)),
+            (52 * WEEK + 1, true, parts(52, 0, 0, 0, 1)),
+        ];
+        for (secs, weeks, expected) in cases.iter() {
+            assert_eq!(Parts::new(*secs, *weeks), *expected, "{}s", secs);
+        }
+    }
+
+    #[test]
+    fn compact() {
+        // (seconds, weeks, precise, seconds shown, expected)
+        let cases = [
+            (0, false, false, false, "0m"),
+            (0, false, true, false, "0m"),
+            (0, false, false, true, "0s"),
+            (42, false, false, false, "0m"),
+            (42, false, false, true, "42s"),
+            (5 * MINUTE, false, false, false, "5m"),
+            (5 * MINUTE + 7, false, true, false, "5m"),
+            (5 * MINUTE + 7, false, true, true, "5m7s"),
+            (HOUR + 5 * MINUTE, false, false, false, "1h"),
+            (HOUR + 5 * MINUTE, false, true, false, "1h5m"),
+            (HOUR + 5 * MINUTE + 7, false, false, true, "1h5m7s"),
+            (5 * HOUR, false, true, false, "5h"),
+            (DAY + 5 * MINUTE, false, false, false, "1d"),
+            (DAY + 5 * MINUTE, false, true, false, "1d"),
+            (DAY + 5 * MINUTE, false, false, true, "1d5m"),
+            (3 * DAY + 4 * HOUR + 12 * MINUTE, false, false, false, "3d"),
+            (3 * DAY + 4 * HOUR + 12 * MINUTE, false, true, false, "3d4h"),
+            (3 * DAY + 4 * HOUR + 12 * MINUTE, true, true, false, "3d4h"),
+            (10 * DAY + 4 * HOUR, false, false, false, "10d"),
+            (10 * DAY + 4 * HOUR, false, true, false, "10d4h"),
+            (10 * DAY + 4 * HOUR, true, false, false, "1w3d"),
+            // hours are kept past the first week
+            (10 * DAY + 4 * HOUR, true, true, false, "1w3d4h"),
+            (2 * WEEK + 4 * HOUR, true, false, false, "2w"),
+            (2 * WEEK + 4 * HOUR, true, true, false, "2w4h"),
+            (2 * WEEK + 4 * HOUR + 3, true, false, true, "2w4h3s"),
+            (3 * WEEK, true, true, false, "3w"),
+        ];
+        for (secs, weeks, precise, seconds, expected) in cases.iter() {
+            assert_eq!(
+                Parts::new(*secs, *weeks).compact(*precise, *seconds),
+                *expected,
+                "{}s, weeks: {}, precise: {}, seconds: {}",
+                secs,
+                weeks,
+                precise,
+                seconds
+            );
+        }
+    }
+
+    #[test]
+    fn long() {
+        // (seconds, weeks, precise, seconds shown, expected)
+        let cases = [
+            (0, false, false, false, "0 minutes"),
+            (MINUTE, false, false, false, "1 minute"),
+            (55 * MINUTE + 13, false, false, true, "55 minutes, 13 seconds"),
+            (HOUR + MINUTE, false, true, false, "1 hour, 1 minute"),
+            (3 * DAY + 4 * HOUR, false, false, false, "3 days"),
+            (3 * DAY + 4 * HOUR, false, true, false, "3 days, 4 hours"),
+            (WEEK + DAY + HOUR, true, true, false, "1 week, 1 day, 1 hour"),
+        ];
+        for (secs, weeks, precise, seconds, expected) in cases.iter() {
+            assert_eq!(
+                Parts::new(*secs, *weeks).long(*precise, *seconds),
+                *expected,
+                "{}s, weeks: {}, precise: {}, seconds: {}",
+                secs,
+                weeks,
+                precise,
+                seconds
+            );
+        }
+    }
+
+    #[test]
+    fn clock() {
+        // (seconds, weeks, seconds shown, expected)
+        let cases = [
+            (0, false, false, "00:00"),
+            (55 * MINUTE + 13, false, true, "00:55:13"),
+            (3 * DAY + 4 * HOUR + 12 * MINUTE, false, false, "3d 04:12"),
+            (10 * DAY + 4 * HOUR, false, false, "10d 04:00"),
+            (10 * DAY + 4 * HOUR, true, false, "1w 3d 04:00"),
+            (2 * WEEK + 59, true, true, "2w 00:00:59"),
+        ];
+        for (secs, weeks, seconds, expected) in cases.iter() {
+            assert_eq!(
+                Parts::new(*secs, *weeks).clock(*seconds),
+                *expected,
+                "{}s, weeks: {}, seconds: {}",
+                secs,
+                weeks,
+                seconds
+            );
+        }
+    }
+
+    #[test]
+    fn strftime() {
+        let uptime = Uptime {
+            seconds: 10 * DAY + 4 * HOUR + 12 * MINUTE + 9,
+        };
+        let cases = [
+            ("%dd %H:%M", "10d 04:12"),
+            ("%ww %Dd %H:%M:%S", "1w 3d 04:12:09"),
+            ("%s", "879129"),
+            ("100%%", "100%"),
+        ];
+        for (template, expected) in cases.iter() {
+            assert_eq!(uptime.strftime(template).unwrap(), *expected);
+        }
+        assert!(uptime.strftime("%q").is_err());
+        assert!(uptime.strftime("%").is_err());
+    }
 }
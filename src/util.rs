@@ -0,0 +1,55 @@
+/* Shared formatting helpers */
+use crate::Result;
+use std::cmp;
+
+/// Convert bytes into human-readable string format
+///
+/// `si_units`: use 1024 instead of 1000 bytes/kilobyte
+/// `display_byte_suffix`: show 'B' after unit (e.g., 'MB' vs 'M')
+pub fn humanize_bytes(num: f64, si_units: bool, display_byte_suffix: bool) -> Result<String> {
+    humanize_bytes_with(num, si_units, display_byte_suffix, 2)
+}
+
+/// Like `humanize_bytes`, with `precision` decimal places
+pub fn humanize_bytes_with(
+    num: f64,
+    si_units: bool,
+    display_byte_suffix: bool,
+    precision: usize,
+) -> Result<String> {
+    let negative = if num.is_sign_positive() { "" } else { "-" };
+    let num = num.abs();
+    let units = ["", "k", "M", "G", "T", "P", "E", "Z", "Y"];
+    let byte_suffix = if display_byte_suffix { "B" } else { "" };
+    if num < 1_f64 {
+        return Ok(format!("{}{:.*}{}", negative, precision, num, byte_suffix));
+    }
+    let delimiter = if si_units { 1024_f64 } else { 1000_f64 };
+    let exponent = cmp::min(
+        (num.ln() / delimiter.ln()).floor() as i32,
+        (units.len() - 1) as i32,
+    );
+    let pretty_bytes = num / delimiter.powi(exponent);
+    let unit = units[exponent as usize];
+    let out = format!("{}{:.*}{}{}", negative, precision, pretty_bytes, unit, byte_suffix);
+    Ok(out)
+}
+
+/// Fresh directory under the temp dir for the fixtures of `test`
+#[cfg(test)]
+pub(crate) fn fixture_dir(test: &str) -> std::path::PathBuf {
+    let name = format!("sysinfo-{}-{}", std::process::id(), test);
+    let dir = std::env::temp_dir().join(name);
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Write each `(file, contents)` pair into `dir`, creating it if needed
+#[cfg(test)]
+pub(crate) fn write_fixture(dir: &std::path::Path, files: &[(&str, &str)]) {
+    std::fs::create_dir_all(dir).unwrap();
+    for (file, contents) in files {
+        std::fs::write(dir.join(file), format!("{}\n", contents)).unwrap();
+    }
+}